@@ -0,0 +1,97 @@
+//! Counts the heap allocations and bytes requested while building one tree of
+//! 100k rectangles in three ways: inserting them one at a time, `Tree::from`,
+//! which sizes its top-level `Vec` from the input length, and
+//! `Tree::with_capacity` followed by `extend`, which sizes it from the hint.
+//! Each node's own R-tree dominates both counts, so the three differ only by
+//! the few dozen reallocations of the top-level `Vec`; `Tree::from` also
+//! briefly holds room for all 100k nodes there. Run with
+//! `cargo bench --bench allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use depth_tree::{Rect, Tree};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// 100 top-level cells in a 10 x 10 grid, each holding 999 small squares,
+/// largest first.
+fn shapes() -> Vec<Rect> {
+    let mut shapes = Vec::new();
+    for cell in 0..100 {
+        let origin = [(cell % 10) as f32 * 100.0, (cell / 10) as f32 * 100.0];
+        shapes.push(Rect::new(origin, [origin[0] + 99.0, origin[1] + 99.0]));
+    }
+    for cell in 0..100 {
+        let origin = [(cell % 10) as f32 * 100.0, (cell / 10) as f32 * 100.0];
+        for i in 0..999 {
+            let min = [
+                origin[0] + (i % 32) as f32 * 3.0 + 1.0,
+                origin[1] + (i / 32) as f32 * 3.0 + 1.0,
+            ];
+            shapes.push(Rect::new(min, [min[0] + 2.0, min[1] + 2.0]));
+        }
+    }
+    shapes
+}
+
+fn measure(name: &str, build: impl FnOnce(Vec<Rect>) -> Tree<Rect>) {
+    let shapes = shapes();
+    let (allocations, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    let start = Instant::now();
+    let tree = build(shapes);
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<24} {:>9} allocations {:>12} bytes {:>8.1?} ({} shapes)",
+        name,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        BYTES.load(Ordering::Relaxed) - bytes,
+        elapsed,
+        tree.len(),
+    );
+}
+
+fn main() {
+    measure("insert one at a time", |shapes| {
+        let mut tree = Tree::from((Vec::new(), Rect::default()));
+        for shape in shapes {
+            tree.insert(shape);
+        }
+        tree
+    });
+    measure("Tree::from", Tree::from);
+    measure("with_capacity + extend", |shapes| {
+        let mut tree = Tree::with_capacity(Rect::default(), 100);
+        tree.extend(shapes);
+        tree
+    });
+}
@@ -0,0 +1,57 @@
+//! Times importing a batch of small SVG documents with `import_many`, which
+//! shares one `usvg::Options`, against calling `import_to_lines` on each
+//! document, which loads the system fonts again every time. Run with
+//! `cargo bench --bench svg_import`.
+
+use std::time::Instant;
+
+use depth_tree::{import_many, import_to_lines, usvg_options};
+
+/// `n` documents of a square and a circle each, shifted so no two are the
+/// same.
+fn documents(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|i| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="96" height="96" viewBox="0 0 96 96">
+                    <rect x="{i}" y="0" width="40" height="40"/>
+                    <circle cx="60" cy="{i}" r="20"/>
+                </svg>"#,
+                i = i % 50,
+            )
+        })
+        .collect()
+}
+
+fn measure(name: &str, n: usize, import: impl FnOnce(Vec<String>) -> usize) {
+    let documents = documents(n);
+    let start = Instant::now();
+    let lines = import(documents);
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<24} {:>10.1?} total {:>10.1?} per document ({} lines)",
+        name,
+        elapsed,
+        elapsed / n as u32,
+        lines,
+    );
+}
+
+fn main() {
+    for n in [10, 100] {
+        measure("import_to_lines loop", n, |documents| {
+            documents
+                .iter()
+                .map(|svg| import_to_lines(svg, 0.1).len())
+                .sum()
+        });
+        measure("import_many", n, |documents| {
+            import_many(documents.into_iter(), 0.1, &usvg_options())
+                .unwrap()
+                .iter()
+                .map(Vec::len)
+                .sum()
+        });
+    }
+}
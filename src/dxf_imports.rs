@@ -0,0 +1,293 @@
+#[cfg(feature = "dxf-integration")]
+use geo::{Coord, LineString, Polygon};
+
+#[cfg(feature = "dxf-integration")]
+#[derive(Debug, Clone, Default)]
+pub struct DxfOptions {
+    /// Close open polylines with a straight segment instead of skipping
+    /// them.
+    pub close_open: bool,
+}
+
+/// Reads the closed `LWPOLYLINE` and `POLYLINE` entities of an ASCII DXF
+/// file as polygons for `Tree::from_polygon`. Bulges (arc segments) are
+/// flattened so no point strays more than `flatten` drawing units from the
+/// arc. Open polylines are skipped unless `options` closes them.
+#[cfg(feature = "dxf-integration")]
+pub fn import_dxf(
+    path: &std::path::Path,
+    flatten: f32,
+    options: &DxfOptions,
+) -> Result<Vec<Polygon>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_dxf(&content, flatten, options)
+}
+
+/// Like [`import_dxf`], for DXF content already in memory. Only entities in
+/// the `ENTITIES` section are read, so block definitions are ignored, as are
+/// 3D meshes and polyface meshes stored as `POLYLINE`s. A `POLYLINE` whose
+/// vertices are not closed by a `SEQEND` is an error.
+#[cfg(feature = "dxf-integration")]
+pub fn parse_dxf(dxf: &str, flatten: f32, options: &DxfOptions) -> Result<Vec<Polygon>, String> {
+    let mut polylines: Vec<DxfPolyline> = Vec::new();
+    let mut in_entities = false;
+    // The open `POLYLINE` collecting `VERTEX` entities until its `SEQEND`.
+    let mut pending: Option<DxfPolyline> = None;
+
+    for (kind, pairs) in entities(dxf)? {
+        if pending.is_some() && !matches!(kind, "VERTEX" | "SEQEND") {
+            return Err(format!("POLYLINE without SEQEND before {}", kind));
+        }
+        match kind {
+            "SECTION" => in_entities = pairs.contains(&(2, "ENTITIES")),
+            "ENDSEC" => in_entities = false,
+            _ if !in_entities => {}
+            "LWPOLYLINE" => {
+                let mut polyline = DxfPolyline::new(flags(&pairs)?);
+                for (code, value) in pairs {
+                    match code {
+                        10 => polyline.vertices.push(([number(value)?, 0.0], 0.0)),
+                        20 | 42 => {
+                            let Some(vertex) = polyline.vertices.last_mut() else {
+                                return Err(format!("LWPOLYLINE code {} before any vertex", code));
+                            };
+                            if code == 20 {
+                                vertex.0[1] = number(value)?;
+                            } else {
+                                vertex.1 = number(value)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                polylines.push(polyline);
+            }
+            "POLYLINE" => pending = Some(DxfPolyline::new(flags(&pairs)?)),
+            "VERTEX" => {
+                if let Some(polyline) = &mut pending {
+                    let mut vertex = ([0.0, 0.0], 0.0);
+                    for (code, value) in pairs {
+                        match code {
+                            10 => vertex.0[0] = number(value)?,
+                            20 => vertex.0[1] = number(value)?,
+                            42 => vertex.1 = number(value)?,
+                            _ => {}
+                        }
+                    }
+                    polyline.vertices.push(vertex);
+                }
+            }
+            "SEQEND" => polylines.extend(pending.take()),
+            _ => {}
+        }
+    }
+    if pending.is_some() {
+        return Err("POLYLINE without SEQEND at end of file".to_string());
+    }
+
+    Ok(polylines
+        .into_iter()
+        // 3D polylines and meshes set flags 8, 16 or 64.
+        .filter(|polyline| polyline.flags & (8 | 16 | 64) == 0)
+        .filter(|polyline| polyline.flags & 1 == 1 || options.close_open)
+        .filter(|polyline| polyline.vertices.len() >= 2)
+        .map(|polyline| Polygon::new(polyline.flatten(flatten as f64), Vec::new()))
+        .collect())
+}
+
+#[cfg(feature = "dxf-integration")]
+struct DxfPolyline {
+    flags: u32,
+    /// Each vertex with the bulge of the segment that starts at it: the
+    /// tangent of a quarter of the arc's included angle, positive for
+    /// counterclockwise arcs and zero for straight segments.
+    vertices: Vec<([f64; 2], f64)>,
+}
+
+#[cfg(feature = "dxf-integration")]
+impl DxfPolyline {
+    fn new(flags: u32) -> Self {
+        Self {
+            flags,
+            vertices: Vec::new(),
+        }
+    }
+
+    fn flatten(&self, tolerance: f64) -> LineString {
+        let closed = self.flags & 1 == 1;
+        let mut coords = Vec::new();
+        for (i, &(from, bulge)) in self.vertices.iter().enumerate() {
+            coords.push(Coord {
+                x: from[0],
+                y: from[1],
+            });
+            match self.vertices.get(i + 1) {
+                Some(&(to, _)) => push_arc(&mut coords, from, to, bulge, tolerance),
+                None if closed => push_arc(&mut coords, from, self.vertices[0].0, bulge, tolerance),
+                None => {}
+            }
+        }
+        LineString::new(coords)
+    }
+}
+
+/// Pushes the points strictly between `from` and `to` on the arc with the
+/// given bulge, spaced so each chord is within `tolerance` of the arc.
+#[cfg(feature = "dxf-integration")]
+fn push_arc(coords: &mut Vec<Coord>, from: [f64; 2], to: [f64; 2], bulge: f64, tolerance: f64) {
+    let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+    let chord = dx.hypot(dy);
+    if bulge == 0.0 || chord == 0.0 || !bulge.is_finite() {
+        return;
+    }
+
+    let sweep = 4.0 * bulge.atan();
+    let radius = chord / (2.0 * (sweep / 2.0).sin()).abs();
+    // The center is on the left of the chord for a counterclockwise arc
+    // shorter than a half circle, and on the right for a longer one.
+    let offset = (1.0 - bulge * bulge) / (4.0 * bulge);
+    let center = [
+        (from[0] + to[0]) / 2.0 - dy * offset,
+        (from[1] + to[1]) / 2.0 + dx * offset,
+    ];
+
+    // A chord spanning angle `a` strays `r * (1 - cos(a / 2))` from the arc.
+    let max_step = if tolerance > 0.0 && tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        std::f64::consts::PI / 2.0
+    };
+    let steps = (sweep.abs() / max_step).ceil().max(1.0) as usize;
+
+    let start = (from[1] - center[1]).atan2(from[0] - center[0]);
+    for step in 1..steps {
+        let angle = start + sweep * step as f64 / steps as f64;
+        coords.push(Coord {
+            x: center[0] + radius * angle.cos(),
+            y: center[1] + radius * angle.sin(),
+        });
+    }
+}
+
+/// An entity's name and its `(group code, value)` pairs.
+#[cfg(feature = "dxf-integration")]
+type DxfEntity<'a> = (&'a str, Vec<(i32, &'a str)>);
+
+/// Splits DXF content into entities: each `0` group code starts one, named
+/// by its value and followed by its other pairs.
+#[cfg(feature = "dxf-integration")]
+fn entities(dxf: &str) -> Result<Vec<DxfEntity<'_>>, String> {
+    let mut lines = dxf.lines().map(str::trim);
+    let mut entities: Vec<DxfEntity> = Vec::new();
+    let mut line = 0;
+    while let Some(code) = lines.next() {
+        line += 1;
+        if code.is_empty() && lines.clone().all(str::is_empty) {
+            break;
+        }
+        let code: i32 = code
+            .parse()
+            .map_err(|_| format!("Invalid DXF group code {:?} on line {}", code, line))?;
+        let value = lines
+            .next()
+            .ok_or_else(|| format!("DXF group code {} on line {} has no value", code, line))?;
+        line += 1;
+
+        if code == 0 {
+            entities.push((value, Vec::new()));
+        } else if let Some((_, pairs)) = entities.last_mut() {
+            pairs.push((code, value));
+        }
+    }
+    Ok(entities)
+}
+
+#[cfg(feature = "dxf-integration")]
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid DXF number {:?}", value))
+}
+
+#[cfg(feature = "dxf-integration")]
+fn flags(pairs: &[(i32, &str)]) -> Result<u32, String> {
+    match pairs.iter().find(|(code, _)| *code == 70) {
+        Some((_, value)) => value
+            .parse()
+            .map_err(|_| format!("Invalid DXF flags {:?}", value)),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn dxf(entities: &[&str]) -> String {
+        let mut content = vec!["0", "SECTION", "2", "ENTITIES"];
+        for entity in entities {
+            content.extend(entity.split_whitespace());
+        }
+        content.extend(["0", "ENDSEC", "0", "EOF"]);
+        content.join("\n")
+    }
+
+    const OUTER: &str = "0 LWPOLYLINE 90 4 70 1 10 0 20 0 10 10 20 0 10 10 20 10 10 0 20 10";
+    const INNER: &str = "0 POLYLINE 66 1 70 1 \
+        0 VERTEX 10 2 20 2 0 VERTEX 10 4 20 2 0 VERTEX 10 4 20 4 0 VERTEX 10 2 20 4 \
+        0 SEQEND";
+    const OPEN: &str = "0 LWPOLYLINE 90 3 70 0 10 20 20 0 10 30 20 0 10 30 20 10";
+
+    #[test]
+    fn parse_dxf_nested_rectangles() {
+        let polygons = parse_dxf(&dxf(&[OUTER, INNER, OPEN]), 0.1, &DxfOptions::default()).unwrap();
+        assert_eq!(polygons.len(), 2);
+
+        let tree = Tree::from_polygon(polygons);
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+        assert_eq!(tree.depth_at_point([3.0, 3.0]), 2);
+
+        let options = DxfOptions { close_open: true };
+        let polygons = parse_dxf(&dxf(&[OUTER, INNER, OPEN]), 0.1, &options).unwrap();
+        assert_eq!(polygons.len(), 3);
+        assert_eq!(polygons[2].area(), 50.0);
+    }
+
+    #[test]
+    fn parse_dxf_flattens_bulges() {
+        // Two half circles of radius 5 around (5, 0).
+        let circle = "0 LWPOLYLINE 90 2 70 1 10 0 20 0 42 1 10 10 20 0 42 1";
+        let coarse = parse_dxf(&dxf(&[circle]), 0.5, &DxfOptions::default()).unwrap();
+        let fine = parse_dxf(&dxf(&[circle]), 0.001, &DxfOptions::default()).unwrap();
+
+        let exact = std::f32::consts::PI * 25.0;
+        assert!(coarse[0].exterior().0.len() < fine[0].exterior().0.len());
+        assert!((fine[0].area() - exact).abs() < 0.05);
+        assert!(fine[0].contains_point([5.0, -4.9]));
+        assert!(fine[0].contains_point([5.0, 4.9]));
+    }
+
+    #[test]
+    fn parse_dxf_errors() {
+        assert!(parse_dxf("0\nSECTION\nx", 0.1, &DxfOptions::default()).is_err());
+        assert!(parse_dxf("0", 0.1, &DxfOptions::default()).is_err());
+        assert!(
+            import_dxf(
+                std::path::Path::new("/missing.dxf"),
+                0.1,
+                &DxfOptions::default()
+            )
+            .is_err()
+        );
+
+        let unterminated = INNER.trim_end_matches("0 SEQEND");
+        for entities in [&[unterminated][..], &[unterminated, OUTER]] {
+            let error = parse_dxf(&dxf(entities), 0.1, &DxfOptions::default()).unwrap_err();
+            assert!(error.contains("SEQEND"), "{}", error);
+        }
+        let content = dxf(&[unterminated]);
+        let truncated = content.trim_end_matches("\n0\nENDSEC\n0\nEOF");
+        assert!(parse_dxf(truncated, 0.1, &DxfOptions::default()).is_err());
+    }
+}
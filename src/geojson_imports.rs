@@ -0,0 +1,125 @@
+#[cfg(feature = "geojson-integration")]
+use geo::Polygon;
+
+#[cfg(feature = "geojson-integration")]
+use geojson::{GeoJson, GeometryValue};
+
+#[cfg(feature = "geojson-integration")]
+pub fn import_geojson(content: &str) -> Result<Vec<Polygon>, geojson::Error> {
+    Ok(import_geojson_with_properties(content)?
+        .into_iter()
+        .map(|(_, polygon)| polygon)
+        .collect())
+}
+
+/// Like [`import_geojson`], but pairs each polygon with the properties of the
+/// feature it came from (`Null` for bare geometries), ready for
+/// `Tree::from_polygon_id`. A `MultiPolygon` yields one entry per polygon.
+#[cfg(feature = "geojson-integration")]
+pub fn import_geojson_with_properties(
+    content: &str,
+) -> Result<Vec<(serde_json::Value, Polygon)>, geojson::Error> {
+    let mut polygons = Vec::new();
+    match content.parse::<GeoJson>()? {
+        GeoJson::Geometry(geometry) => {
+            push_polygons(&geometry.value, &serde_json::Value::Null, &mut polygons)?
+        }
+        GeoJson::Feature(feature) => push_feature(feature, &mut polygons)?,
+        GeoJson::FeatureCollection(collection) => {
+            for feature in collection.features {
+                push_feature(feature, &mut polygons)?;
+            }
+        }
+    }
+    Ok(polygons)
+}
+
+#[cfg(feature = "geojson-integration")]
+fn push_feature(
+    feature: geojson::Feature,
+    polygons: &mut Vec<(serde_json::Value, Polygon)>,
+) -> Result<(), geojson::Error> {
+    let Some(geometry) = feature.geometry else {
+        return Ok(());
+    };
+    let properties = feature
+        .properties
+        .map(serde_json::Value::Object)
+        .unwrap_or(serde_json::Value::Null);
+    push_polygons(&geometry.value, &properties, polygons)
+}
+
+#[cfg(feature = "geojson-integration")]
+fn push_polygons(
+    value: &GeometryValue,
+    properties: &serde_json::Value,
+    polygons: &mut Vec<(serde_json::Value, Polygon)>,
+) -> Result<(), geojson::Error> {
+    match value {
+        GeometryValue::Polygon { .. } => {
+            polygons.push((properties.clone(), Polygon::try_from(value)?));
+        }
+        GeometryValue::MultiPolygon { .. } => {
+            for polygon in geo::MultiPolygon::try_from(value)? {
+                polygons.push((properties.clone(), polygon));
+            }
+        }
+        GeometryValue::GeometryCollection { geometries } => {
+            for geometry in geometries {
+                push_polygons(&geometry.value, properties, polygons)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn import_geojson_skips_non_polygons() {
+        let content = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "properties": { "name": "outer" },
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]]]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "name": "inner" },
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [
+                            [[[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]]],
+                            [[[5, 5], [6, 5], [6, 6], [5, 6], [5, 5]]]
+                        ]
+                    }
+                },
+                { "type": "Feature", "properties": null, "geometry": null },
+                {
+                    "type": "Feature",
+                    "properties": null,
+                    "geometry": { "type": "Point", "coordinates": [3, 3] }
+                }
+            ]
+        }"#;
+
+        assert_eq!(import_geojson(content).unwrap().len(), 3);
+
+        let tree = Tree::from_polygon_id(import_geojson_with_properties(content).unwrap());
+        let depths: Vec<(usize, &str)> = tree
+            .iter()
+            .map(|(depth, (properties, _))| (depth, properties["name"].as_str().unwrap()))
+            .collect();
+        assert_eq!(depths, vec![(0, "outer"), (1, "inner"), (1, "inner")]);
+
+        assert!(import_geojson("{ not json").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(feature = "image-integration")]
+use geo::Polygon;
+
+#[cfg(feature = "image-integration")]
+use crate::Tree;
+
+/// Renders the tree into a `width` x `height` image, scaled uniformly to fit
+/// the tree's bounding rect. Each polygon is filled (even-odd, so holes stay
+/// clear) with its [`depth_color`](crate::depth_color); containers are drawn
+/// before their descendants so inner shapes paint over them.
+#[cfg(feature = "image-integration")]
+pub fn render_tree(tree: &Tree<Polygon>, width: u32, height: u32) -> image::RgbaImage {
+    let mut image = image::RgbaImage::new(width, height);
+
+    let Some((min, max)) = tree.bounding_rect() else {
+        return image;
+    };
+
+    let (tree_width, tree_height) = ((max[0] - min[0]) as f64, (max[1] - min[1]) as f64);
+    let scale = (width as f64 / tree_width).min(height as f64 / tree_height);
+    if !scale.is_finite() {
+        return image;
+    }
+    let offset_x = (width as f64 - tree_width * scale) / 2.0;
+    let offset_y = (height as f64 - tree_height * scale) / 2.0;
+    let to_image = |coord: &geo::Coord| {
+        (
+            offset_x + (coord.x - min[0] as f64) * scale,
+            offset_y + (max[1] as f64 - coord.y) * scale,
+        )
+    };
+
+    let max_depth = tree.iter().map(|(depth, _)| depth).max().unwrap_or(0);
+    for (depth, polygon) in tree.iter() {
+        let color = image::Rgba(crate::depth_color(depth, max_depth));
+
+        let edges: Vec<((f64, f64), (f64, f64))> = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .flat_map(|ring| ring.lines())
+            .map(|line| (to_image(&line.start), to_image(&line.end)))
+            .collect();
+
+        for y in 0..height {
+            let scan_y = y as f64 + 0.5;
+            let mut crossings: Vec<f64> = edges
+                .iter()
+                .filter(|((_, y1), (_, y2))| (*y1 <= scan_y) != (*y2 <= scan_y))
+                .map(|((x1, y1), (x2, y2))| x1 + (scan_y - y1) / (y2 - y1) * (x2 - x1))
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil().max(0.0) as u32;
+                let end = ((span[1] - 0.5).floor() + 1.0).clamp(0.0, width as f64) as u32;
+                for x in start..end {
+                    image.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::square;
+    use crate::*;
+
+    #[test]
+    fn render_tree_paints_inner_over_outer() {
+        let tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(4.0, 4.0, 2.0)]);
+        let image = render_tree(&tree, 100, 100);
+
+        let outer = image.get_pixel(10, 10);
+        let inner = image.get_pixel(50, 50);
+        assert_eq!(outer.0[3], 255);
+        assert_eq!(outer.0, depth_color(0, 1));
+        assert_eq!(inner.0, depth_color(1, 1));
+    }
+}
@@ -0,0 +1,186 @@
+#[cfg(feature = "json-integration")]
+use serde_json::{Value, json};
+
+#[cfg(feature = "json-integration")]
+use crate::{Shape, Tree, TreeNode};
+
+#[cfg(feature = "json-integration")]
+impl<T> Tree<T>
+where
+    T: Shape + Clone,
+{
+    /// Array of the top-level nodes as `{ "value": ..., "children": [...] }`
+    /// objects, with each value mapped by `value_fn`. Internal fields such as
+    /// bounds and area are left out.
+    pub fn to_nested_json<F>(&self, value_fn: F) -> Value
+    where
+        F: Fn(&T) -> Value,
+    {
+        let children = match self.root() {
+            Some(root) => root.children(),
+            None => Vec::new(),
+        };
+        Value::Array(
+            children
+                .into_iter()
+                .map(|child| node_to_json(child, &value_fn))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "json-integration")]
+impl<T> Tree<T>
+where
+    T: Shape + Clone,
+{
+    /// Rebuilds a tree from the output of [`Tree::to_nested_json`], keeping
+    /// the stored nesting instead of re-deriving it from geometry. Each
+    /// `"value"` is turned back into a `T` by `parse`; `root` is the value of
+    /// the synthetic root, as in `Tree::from((values, root))`. A value that
+    /// parses to an [empty](Shape::is_empty) shape is an error, since the
+    /// stored nesting cannot skip it without losing its children.
+    pub fn from_nested_json<F>(json: &Value, root: T, parse: F) -> Result<Tree<T>, String>
+    where
+        F: Fn(&Value) -> T,
+    {
+        Ok(Tree::from_top_level(root, nodes_from_json(json, &parse)?))
+    }
+}
+
+#[cfg(feature = "json-integration")]
+fn nodes_from_json<T, F>(json: &Value, parse: &F) -> Result<Vec<TreeNode<T>>, String>
+where
+    T: Shape + Clone,
+    F: Fn(&Value) -> T,
+{
+    let Some(nodes) = json.as_array() else {
+        return Err(format!("Expected an array of nodes, found {}", json));
+    };
+
+    nodes
+        .iter()
+        .map(|node| {
+            let Some(value) = node.get("value") else {
+                return Err(format!("Node is missing \"value\": {}", node));
+            };
+            let value = parse(value);
+            if value.is_empty() {
+                return Err(format!("Node value is an empty shape: {}", node));
+            }
+            let children = match node.get("children") {
+                Some(children) => nodes_from_json(children, parse)?,
+                None => Vec::new(),
+            };
+            Ok(TreeNode::with_children(value, children))
+        })
+        .collect()
+}
+
+#[cfg(feature = "json-integration")]
+fn node_to_json<T, F>(node: &TreeNode<T>, value_fn: &F) -> Value
+where
+    T: Shape + Clone,
+    F: Fn(&T) -> Value,
+{
+    let children: Vec<Value> = node
+        .children()
+        .into_iter()
+        .map(|child| node_to_json(child, value_fn))
+        .collect();
+    json!({
+        "value": value_fn(node.value()),
+        "children": children,
+    })
+}
+
+#[cfg(all(test, feature = "geo-integration"))]
+mod tests {
+    use crate::test_support::square;
+    use crate::*;
+    use geo::Polygon;
+    use serde_json::{Value, json};
+
+    /// Three nested squares with ids 1 to 3, and a fourth one beside them.
+    fn nested_squares() -> Tree<(i64, Polygon)> {
+        Tree::from_polygon_id(vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, square(1.0, 1.0, 4.0)),
+            (3, square(2.0, 2.0, 1.0)),
+            (4, square(20.0, 0.0, 5.0)),
+        ])
+    }
+
+    fn depth(nodes: &Value) -> usize {
+        nodes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| depth(&node["children"]) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn to_nested_json_mirrors_hierarchy() {
+        let tree = nested_squares();
+
+        let nested = tree.to_nested_json(|(id, _)| json!(id));
+        assert_eq!(nested.as_array().unwrap().len(), 2);
+        assert_eq!(depth(&nested), 3);
+
+        let outer = nested
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|node| node["value"] == json!(1))
+            .unwrap();
+        assert_eq!(outer["children"][0]["value"], json!(2));
+        assert_eq!(outer["children"][0]["children"][0]["value"], json!(3));
+    }
+
+    #[test]
+    fn from_nested_json_round_trips() {
+        let tree = nested_squares();
+        let polygons: Vec<(i64, Polygon)> =
+            tree.iter().map(|(_, (id, p))| (*id, p.clone())).collect();
+
+        let nested = tree.to_nested_json(|(id, _)| json!(id));
+        let root = (
+            0,
+            Polygon::new(geo::LineString::new(Vec::new()), Vec::new()),
+        );
+        let rebuilt = Tree::from_nested_json(&nested, root.clone(), |value| {
+            let id = value.as_i64().unwrap();
+            polygons
+                .iter()
+                .find(|(other, _)| *other == id)
+                .unwrap()
+                .clone()
+        })
+        .unwrap();
+        assert_eq!(rebuilt, tree);
+        assert_eq!(rebuilt.depth_at_point([2.5, 2.5]), 3);
+
+        assert!(
+            Tree::<(i64, Polygon)>::from_nested_json(&json!({}), root.clone(), |_| unreachable!())
+                .is_err()
+        );
+        assert!(
+            Tree::<(i64, Polygon)>::from_nested_json(
+                &json!([{ "children": [] }]),
+                root.clone(),
+                |_| unreachable!()
+            )
+            .is_err()
+        );
+        assert!(
+            Tree::from_nested_json(
+                &json!([{ "value": 5, "children": [] }]),
+                root.clone(),
+                |_| { root.clone() }
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,8 +1,56 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod float;
+
+#[cfg(all(test, feature = "geo-integration"))]
+mod test_support;
+
 pub mod tree;
 pub use tree::*;
 
+pub mod shapes;
+pub use shapes::*;
+
+pub mod viz;
+pub use viz::*;
+
 #[cfg(feature = "svg-integration")]
 pub mod svg_imports;
 
 #[cfg(feature = "svg-integration")]
 pub use svg_imports::*;
+
+#[cfg(feature = "geojson-integration")]
+pub mod geojson_imports;
+
+#[cfg(feature = "geojson-integration")]
+pub use geojson_imports::*;
+
+#[cfg(feature = "wkt-integration")]
+pub mod wkt_imports;
+
+#[cfg(feature = "wkt-integration")]
+pub use wkt_imports::*;
+
+#[cfg(feature = "wkb-integration")]
+pub mod wkb_imports;
+
+#[cfg(feature = "wkb-integration")]
+pub use wkb_imports::*;
+
+#[cfg(feature = "dxf-integration")]
+pub mod dxf_imports;
+
+#[cfg(feature = "dxf-integration")]
+pub use dxf_imports::*;
+
+#[cfg(feature = "image-integration")]
+pub mod image_exports;
+
+#[cfg(feature = "image-integration")]
+pub use image_exports::*;
+
+#[cfg(feature = "json-integration")]
+pub mod json_exports;
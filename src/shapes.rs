@@ -0,0 +1,162 @@
+use crate::float;
+use crate::{Shape, Transform};
+
+/// Axis-aligned rectangle from `min` to `max`, edges included.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Rect {
+    pub fn new(min: [f32; 2], max: [f32; 2]) -> Self {
+        Self { min, max }
+    }
+}
+
+impl Shape for Rect {
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        self.min[0] <= rhs.min[0]
+            && self.min[1] <= rhs.min[1]
+            && rhs.max[0] <= self.max[0]
+            && rhs.max[1] <= self.max[1]
+    }
+
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        (self.min[0]..=self.max[0]).contains(&point[0])
+            && (self.min[1]..=self.max[1]).contains(&point[1])
+    }
+
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        (self.min, self.max)
+    }
+
+    fn center_point(&self) -> [f32; 2] {
+        [
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+        ]
+    }
+
+    fn area(&self) -> f32 {
+        (self.max[0] - self.min[0]) * (self.max[1] - self.min[1])
+    }
+}
+
+impl Transform for Rect {
+    /// Bounding box of the transformed corners, which is exact for scales
+    /// and translations.
+    fn transform(&self, [a, b, c, d, e, f]: [f32; 6]) -> Self {
+        let corners = [
+            self.min,
+            [self.max[0], self.min[1]],
+            self.max,
+            [self.min[0], self.max[1]],
+        ]
+        .map(|[x, y]| [a * x + c * y + e, b * x + d * y + f]);
+
+        let mut rect = Rect::new(corners[0], corners[0]);
+        for [x, y] in corners {
+            rect.min = [rect.min[0].min(x), rect.min[1].min(y)];
+            rect.max = [rect.max[0].max(x), rect.max[1].max(y)];
+        }
+        rect
+    }
+}
+
+/// Disk of `radius` around `center`, boundary included.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Circle {
+    pub center: [f32; 2],
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: [f32; 2], radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    fn distance(&self, point: [f32; 2]) -> f32 {
+        float::hypot(point[0] - self.center[0], point[1] - self.center[1])
+    }
+}
+
+impl Shape for Circle {
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        self.distance(rhs.center) + rhs.radius <= self.radius
+    }
+
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        self.distance(point) <= self.radius
+    }
+
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        (
+            [self.center[0] - self.radius, self.center[1] - self.radius],
+            [self.center[0] + self.radius, self.center[1] + self.radius],
+        )
+    }
+
+    fn center_point(&self) -> [f32; 2] {
+        self.center
+    }
+
+    fn area(&self) -> f32 {
+        core::f32::consts::PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f32 {
+        2.0 * core::f32::consts::PI * self.radius
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        self.distance(other.center) <= self.radius + other.radius
+    }
+
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        (self.distance(point) - self.radius).max(0.0)
+    }
+}
+
+impl Transform for Circle {
+    /// Moves the center and scales the radius by the square root of the
+    /// determinant, which is exact for rotations, uniform scales and
+    /// translations.
+    fn transform(&self, [a, b, c, d, e, f]: [f32; 6]) -> Self {
+        let [x, y] = self.center;
+        Circle::new(
+            [a * x + c * y + e, b * x + d * y + f],
+            self.radius * float::sqrt((a * d - b * c).abs()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn circle_containment_is_exact() {
+        let outer = Circle::new([0.0, 0.0], 10.0);
+        assert!(outer.contains_shape(&Circle::new([5.0, 0.0], 5.0)));
+        assert!(!outer.contains_shape(&Circle::new([6.0, 0.0], 5.0)));
+        assert!(outer.intersects(&Circle::new([14.0, 0.0], 5.0)));
+        assert!(!outer.intersects(&Circle::new([16.0, 0.0], 5.0)));
+        assert_eq!(outer.distance_to_point([0.0, 13.0]), 3.0);
+        assert_eq!(outer.distance_to_point([0.0, 3.0]), 0.0);
+
+        let moved = outer.transform([2.0, 0.0, 0.0, 2.0, 1.0, 1.0]);
+        assert_eq!(moved, Circle::new([1.0, 1.0], 20.0));
+    }
+
+    #[test]
+    fn rect_transform_bounds_corners() {
+        let rect = Rect::new([0.0, 0.0], [2.0, 1.0]);
+        assert_eq!(
+            rect.transform([0.0, 1.0, -1.0, 0.0, 0.0, 0.0]),
+            Rect::new([-1.0, 0.0], [0.0, 2.0])
+        );
+        assert!(rect.contains_shape(&Rect::new([0.0, 0.0], [1.0, 1.0])));
+        assert!(!rect.contains_shape(&Rect::new([1.0, 0.0], [3.0, 1.0])));
+    }
+}
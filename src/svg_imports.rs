@@ -1,5 +1,8 @@
 #[cfg(feature = "svg-integration")]
-use geo::LineString;
+use geo::{LineString, Polygon};
+
+#[cfg(feature = "svg-integration")]
+use crate::Tree;
 
 #[cfg(feature = "svg-integration")]
 pub fn import_svg(path: &std::path::Path, flatten: f32) -> Option<Vec<LineString>> {
@@ -9,72 +12,356 @@ pub fn import_svg(path: &std::path::Path, flatten: f32) -> Option<Vec<LineString
     Some(import_to_lines(&content, flatten))
 }
 
+#[cfg(feature = "svg-integration")]
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Drop paths that would not be drawn: hidden paths, paths with neither
+    /// fill nor stroke, and paths whose paint or group opacity is zero.
+    pub skip_invisible: bool,
+    /// Replace each stroked path without a fill by the outline of its
+    /// stroke, so thick lines become areas. lyon tessellates the stroke
+    /// with its width, caps, joins and miter limit, and the triangles are
+    /// merged into polygons. Each polygon gives its outer ring, then one
+    /// ring per hole: a closed subpath gives the outer and inner edges of
+    /// the stroke, which `Tree::from_polygon` nests so the inner edge can
+    /// hold whatever is drawn inside. Dashes are not reproduced.
+    pub outline_strokes: bool,
+}
+
+/// Like [`import_svg`], but picks the flattening tolerance per path so that
+/// curved paths come out with roughly `target_points_per_path` points,
+/// whatever the document's scale. See [`import_to_lines_auto`].
+#[cfg(feature = "svg-integration")]
+pub fn import_svg_auto(
+    path: &std::path::Path,
+    target_points_per_path: usize,
+) -> Option<Vec<LineString>> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return None;
+    };
+    Some(import_to_lines_auto(&content, target_points_per_path))
+}
+
+/// Flattens each path with a tolerance relative to its own size. A circle of
+/// diameter `d` flattened with tolerance `t` gives about `pi * sqrt(d / 4t)`
+/// segments, so each path uses `t = d * pi^2 / (4 * n^2)` with `d` the
+/// diagonal of its bounding box and `n` the target. Straight segments are
+/// never split, so polygons with few corners stay small.
+#[cfg(feature = "svg-integration")]
+pub fn import_to_lines_auto(svg: &str, target_points_per_path: usize) -> Vec<LineString> {
+    let target = target_points_per_path.max(3) as f32;
+    let tolerance = |path: &lyon::path::Path| {
+        let bounds = lyon::algorithms::aabb::fast_bounding_box(path.iter());
+        let diagonal = (bounds.max - bounds.min).length();
+
+        let tolerance = diagonal * std::f32::consts::PI.powi(2) / (4.0 * target * target);
+        if tolerance > 0.0 { tolerance } else { 1.0 }
+    };
+
+    import_to_lines_with_id(svg, &tolerance, &ImportOptions::default())
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// Like [`import_to_lines`] for a batch of documents, parsing them all with
+/// `options` instead of building fresh [`usvg_options`] (and reloading the
+/// system fonts) for each one. Stops at the first document usvg cannot
+/// parse and returns its error.
+#[cfg(feature = "svg-integration")]
+pub fn import_many(
+    svgs: impl Iterator<Item = String>,
+    flatten: f32,
+    options: &usvg::Options,
+) -> Result<Vec<Vec<LineString>>, usvg::Error> {
+    let options = options.to_ref();
+    svgs.map(|svg| {
+        let tree = usvg::Tree::from_str(&svg, &options)?;
+        Ok(
+            lines_from_tree(&tree, &|_| flatten, &ImportOptions::default())
+                .into_iter()
+                .map(|(_, line)| line)
+                .collect(),
+        )
+    })
+    .collect()
+}
+
 #[cfg(feature = "svg-integration")]
 pub fn import_to_lines(svg: &str, flatten: f32) -> Vec<LineString> {
-    use geo::coord;
+    import_to_lines_with_options(svg, flatten, &ImportOptions::default())
+}
 
-    let tree = usvg::Tree::from_str(svg, &usvg_options().to_ref()).expect("Could not read svg");
+#[cfg(feature = "svg-integration")]
+pub fn import_to_lines_with_options(
+    svg: &str,
+    flatten: f32,
+    options: &ImportOptions,
+) -> Vec<LineString> {
+    import_to_lines_with_id(svg, &|_| flatten, options)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect()
+}
 
-    let svg = tree.svg_node();
-    let vb = svg.view_box.rect;
+/// Like [`import_to_lines`], but keeps each path's `id` attribute and closes
+/// the lines into polygons, ready for `Tree::from_polygon_id`. Paths without
+/// an id are named `path{n}` after their position in the document; every
+/// subpath of a path shares its id.
+#[cfg(feature = "svg-integration")]
+pub fn import_to_polygons_with_id(svg: &str, flatten: f32) -> Vec<(String, Polygon)> {
+    import_to_lines_with_id(svg, &|_| flatten, &ImportOptions::default())
+        .into_iter()
+        .map(|(id, line)| (id, Polygon::new(line, Vec::new())))
+        .collect()
+}
+
+#[cfg(feature = "svg-integration")]
+fn import_to_lines_with_id(
+    svg: &str,
+    flatten: &dyn Fn(&lyon::path::Path) -> f32,
+    options: &ImportOptions,
+) -> Vec<(String, LineString)> {
+    let tree = usvg::Tree::from_str(svg, &usvg_options().to_ref()).expect("Could not read svg");
+    lines_from_tree(&tree, flatten, options)
+}
 
-    let width_in_inches = svg.size.width() / 96.0;
-    let height_in_inches = svg.size.height() / 96.0;
+#[cfg(feature = "svg-integration")]
+fn lines_from_tree(
+    tree: &usvg::Tree,
+    flatten: &dyn Fn(&lyon::path::Path) -> f32,
+    options: &ImportOptions,
+) -> Vec<(String, LineString)> {
+    use geo::coord;
+    use usvg::NodeExt;
 
-    let scale_x = width_in_inches / vb.width();
-    let scale_y = height_in_inches / vb.height();
+    let (scale_x, scale_y) = document_scale(&tree.svg_node());
 
     let root = tree.root();
-    let mut line_strings: Vec<LineString> = Vec::new();
+    let mut line_strings: Vec<(String, LineString)> = Vec::new();
     let mut points = Vec::new();
+    let mut path_index = 0;
     for n in root.descendants() {
         if let usvg::NodeKind::Path(ref p) = *n.borrow() {
-            let path = lyon_path_from_data(&p.data);
-
-            use lyon::path::iterator::PathIterator;
-            let flattened_iter = path.iter().flattened(flatten);
-            for evt in flattened_iter {
-                match evt {
-                    lyon::path::PathEvent::Begin { at } => {
-                        points.push(coord! { x: at.x as f64 * scale_x, y: -at.y as f64 * scale_y});
-                    }
-                    lyon::path::PathEvent::Line { from: _, to } => {
-                        points.push(coord! { x: to.x as f64 * scale_x, y: -to.y as f64 * scale_y});
-                    }
-                    lyon::path::PathEvent::End {
-                        last: _,
-                        first,
-                        close: _,
-                    } => {
-                        points.push(
-                            coord! { x: first.x as f64 * scale_x, y: -first.y as f64 * scale_y},
-                        );
-                        line_strings.push(LineString::new(points.clone()));
-                        points.clear();
-                    }
-                    _ => {
-                        panic!()
+            let id = if p.id.is_empty() {
+                format!("path{}", path_index)
+            } else {
+                p.id.clone()
+            };
+            path_index += 1;
+            if options.skip_invisible && !is_visible(&n, p) {
+                continue;
+            }
+
+            let transform = n.abs_transform();
+            let origin = path_origin(&p.data, &transform);
+            let path = lyon_path_from_data(&p.data, &transform, origin);
+            let stroke = p
+                .stroke
+                .as_ref()
+                .filter(|_| options.outline_strokes && p.fill.is_none());
+
+            let mut rings = Vec::new();
+            if let Some(stroke) = stroke {
+                // The path is already transformed, so the width scales with
+                // the transform's area.
+                let scale = (transform.a * transform.d - transform.b * transform.c)
+                    .abs()
+                    .sqrt();
+                let width = (stroke.width.value() * scale) as f32;
+                rings = stroke_outline(&path, stroke, width, flatten(&path));
+            } else {
+                use lyon::path::iterator::PathIterator;
+                let flattened_iter = path.iter().flattened(flatten(&path));
+                for evt in flattened_iter {
+                    match evt {
+                        lyon::path::PathEvent::Begin { at } => {
+                            points.push([at.x, at.y]);
+                        }
+                        lyon::path::PathEvent::Line { from: _, to } => {
+                            points.push([to.x, to.y]);
+                        }
+                        lyon::path::PathEvent::End { .. } => {
+                            rings.push(std::mem::take(&mut points));
+                        }
+                        _ => {
+                            panic!()
+                        }
                     }
                 }
             }
+
+            for ring in rings {
+                let mut coords: Vec<_> = ring
+                    .iter()
+                    .map(|[x, y]| {
+                        coord! {
+                            x: (*x as f64 + origin.0) * scale_x,
+                            y: -(*y as f64 + origin.1) * scale_y,
+                        }
+                    })
+                    .collect();
+                coords.extend(coords.first().copied());
+                line_strings.push((id.clone(), LineString::new(coords)));
+            }
         }
     }
 
     line_strings
 }
 
+/// Rings around the stroke of `path`, `width` wide, as described on
+/// [`ImportOptions::outline_strokes`]. Overlapping parts of the stroke, such
+/// as tight curves and sharp turns, are merged rather than left as rings
+/// that cross themselves.
+#[cfg(feature = "svg-integration")]
+fn stroke_outline(
+    path: &lyon::path::Path,
+    stroke: &usvg::Stroke,
+    width: f32,
+    tolerance: f32,
+) -> Vec<Vec<[f32; 2]>> {
+    use lyon::tessellation::{
+        BuffersBuilder, LineCap, LineJoin, StrokeOptions, StrokeTessellator, StrokeVertex,
+        VertexBuffers,
+    };
+
+    if width.is_nan() || width <= 0.0 {
+        return Vec::new();
+    }
+    let cap = match stroke.linecap {
+        usvg::LineCap::Butt => LineCap::Butt,
+        usvg::LineCap::Round => LineCap::Round,
+        usvg::LineCap::Square => LineCap::Square,
+    };
+    let join = match stroke.linejoin {
+        usvg::LineJoin::Miter => LineJoin::Miter,
+        usvg::LineJoin::Round => LineJoin::Round,
+        usvg::LineJoin::Bevel => LineJoin::Bevel,
+    };
+    let options = StrokeOptions::tolerance(tolerance)
+        .with_line_width(width)
+        .with_line_cap(cap)
+        .with_line_join(join)
+        .with_miter_limit((stroke.miterlimit.value() as f32).max(1.0));
+
+    let mut buffers: VertexBuffers<[f32; 2], u32> = VertexBuffers::new();
+    let mut builder = BuffersBuilder::new(&mut buffers, |vertex: StrokeVertex| {
+        vertex.position().to_array()
+    });
+    if StrokeTessellator::new()
+        .tessellate_path(path, &options, &mut builder)
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    // The union picks its fill rule from the first ring's winding, so every
+    // triangle is turned counterclockwise and slivers are dropped.
+    let triangles: Vec<Polygon> = buffers
+        .indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| {
+                let [x, y] = buffers.vertices[triangle[i] as usize];
+                (x as f64, y as f64)
+            });
+            let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+            if !cross.is_normal() {
+                return None;
+            }
+            let (b, c) = if cross > 0.0 { (b, c) } else { (c, b) };
+            Some(Polygon::new(LineString::from(vec![a, b, c, a]), Vec::new()))
+        })
+        .collect();
+
+    geo::unary_union(&triangles)
+        .into_iter()
+        .flat_map(|polygon| {
+            let (exterior, interiors) = polygon.into_inner();
+            std::iter::once(exterior).chain(interiors)
+        })
+        .map(|ring| {
+            let mut points: Vec<[f32; 2]> =
+                ring.0.iter().map(|c| [c.x as f32, c.y as f32]).collect();
+            points.pop();
+            points
+        })
+        .collect()
+}
+
 #[cfg(feature = "svg-integration")]
-fn lyon_path_from_data(data: &usvg::PathData) -> lyon::path::Path {
+fn is_visible(node: &usvg::Node, path: &usvg::Path) -> bool {
+    let painted = path
+        .fill
+        .iter()
+        .map(|fill| fill.opacity.value())
+        .chain(path.stroke.iter().map(|stroke| stroke.opacity.value()))
+        .any(|opacity| opacity > 0.0);
+    let groups_opaque = node.ancestors().all(|ancestor| match *ancestor.borrow() {
+        usvg::NodeKind::Group(ref group) => group.opacity.value() > 0.0,
+        _ => true,
+    });
+
+    path.visibility == usvg::Visibility::Visible && painted && groups_opaque
+}
+
+/// Inches per user unit along x and y. The viewBox maps user units onto the
+/// document size; usvg substitutes the document size for a missing viewBox,
+/// and if the ratio is still degenerate user units are taken to be CSS
+/// pixels (96 per inch).
+#[cfg(feature = "svg-integration")]
+fn document_scale(svg: &usvg::Svg) -> (f64, f64) {
+    let vb = svg.view_box.rect;
+    let scale_x = svg.size.width() / 96.0 / vb.width();
+    let scale_y = svg.size.height() / 96.0 / vb.height();
+
+    if scale_x.is_finite() && scale_x > 0.0 && scale_y.is_finite() && scale_y > 0.0 {
+        (scale_x, scale_y)
+    } else {
+        (1.0 / 96.0, 1.0 / 96.0)
+    }
+}
+
+/// The first point of `data` after `transform`, which the path's `f32`
+/// lyon points are taken relative to. Coordinates far from zero then only
+/// lose precision relative to the size of the path, not to their magnitude.
+#[cfg(feature = "svg-integration")]
+fn path_origin(data: &usvg::PathData, transform: &usvg::Transform) -> (f64, f64) {
+    data.0
+        .iter()
+        .find_map(|cmd| match cmd {
+            usvg::PathSegment::MoveTo { x, y } => Some(transform.apply(*x, *y)),
+            _ => None,
+        })
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Converts path data to a lyon path, applying `transform` to every point
+/// in `f64` and then subtracting `origin`. Affine transforms map Bézier
+/// control points to the transformed curve, so flattening afterwards keeps
+/// the tolerance in document units.
+#[cfg(feature = "svg-integration")]
+fn lyon_path_from_data(
+    data: &usvg::PathData,
+    transform: &usvg::Transform,
+    origin: (f64, f64),
+) -> lyon::path::Path {
     use lyon::geom::euclid;
 
+    let point = |x: f64, y: f64| {
+        let (x, y) = transform.apply(x, y);
+        euclid::point2((x - origin.0) as f32, (y - origin.1) as f32)
+    };
+
     let mut path = lyon::path::Path::svg_builder();
     for cmd in data.0.iter() {
         match cmd {
             usvg::PathSegment::MoveTo { x, y } => {
-                path.move_to(euclid::point2(*x as f32, *y as f32));
+                path.move_to(point(*x, *y));
             }
             usvg::PathSegment::LineTo { x, y } => {
-                path.line_to(euclid::point2(*x as f32, *y as f32));
+                path.line_to(point(*x, *y));
             }
             usvg::PathSegment::ClosePath => {
                 path.close();
@@ -87,10 +374,7 @@ fn lyon_path_from_data(data: &usvg::PathData) -> lyon::path::Path {
                 x,
                 y,
             } => {
-                let ctrl1 = euclid::point2(*x1 as f32, *y1 as f32);
-                let ctrl2 = euclid::point2(*x2 as f32, *y2 as f32);
-                let to = euclid::point2(*x as f32, *y as f32);
-                path.cubic_bezier_to(ctrl1, ctrl2, to);
+                path.cubic_bezier_to(point(*x1, *y1), point(*x2, *y2), point(*x, *y));
             }
         }
     }
@@ -114,3 +398,331 @@ pub fn usvg_options() -> usvg::Options {
     // options.fontdb.load_font_data(test_font);
     options
 }
+
+#[cfg(feature = "svg-integration")]
+#[derive(Debug, Clone)]
+pub struct ExportStyle {
+    /// Fill colors indexed by depth, wrapping around for deeper nodes. When
+    /// empty, each depth gets its [`depth_color`](crate::depth_color).
+    pub fills: Vec<String>,
+    pub stroke: String,
+    pub stroke_width: f32,
+    pub fill_opacity: f32,
+}
+
+#[cfg(feature = "svg-integration")]
+impl Default for ExportStyle {
+    fn default() -> Self {
+        Self {
+            fills: Vec::new(),
+            stroke: "black".to_string(),
+            stroke_width: 0.01,
+            fill_opacity: 0.5,
+        }
+    }
+}
+
+/// Writes the tree as an SVG document with one `<path>` per polygon, filled by
+/// depth. Coordinates are flipped back to SVG's downward y axis and the
+/// document is sized in inches so `import_to_lines` reads it back unchanged.
+#[cfg(feature = "svg-integration")]
+pub fn export_svg(tree: &Tree<Polygon>, style: ExportStyle) -> String {
+    use svg::node::element::path::Data;
+
+    let (min, max) = tree.bounding_rect().unwrap_or(([0.0, 0.0], [0.0, 0.0]));
+    let (width, height) = (max[0] - min[0], max[1] - min[1]);
+
+    let max_depth = tree.iter().map(|(depth, _)| depth).max().unwrap_or(0);
+    let mut document = svg::Document::new()
+        .set("viewBox", (min[0], -max[1], width, height))
+        .set("width", format!("{}in", width))
+        .set("height", format!("{}in", height));
+
+    for (depth, polygon) in tree.iter() {
+        let mut data = Data::new();
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            let mut coords = ring.coords();
+            let Some(first) = coords.next() else {
+                continue;
+            };
+            data = data.move_to((first.x, -first.y));
+            for coord in coords {
+                data = data.line_to((coord.x, -coord.y));
+            }
+            data = data.close();
+        }
+
+        let fill = if style.fills.is_empty() {
+            let [r, g, b, _] = crate::depth_color(depth, max_depth);
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            style.fills[depth % style.fills.len()].clone()
+        };
+
+        document = document.add(
+            svg::node::element::Path::new()
+                .set("d", data)
+                .set("fill", fill)
+                .set("fill-opacity", style.fill_opacity)
+                .set("fill-rule", "evenodd")
+                .set("stroke", style.stroke.as_str())
+                .set("stroke-width", style.stroke_width),
+        );
+    }
+
+    document.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::square;
+    use crate::*;
+    use geo::{Area, Polygon};
+
+    #[test]
+    fn export_svg_round_trips() {
+        let tree = Tree::from_polygon(vec![square(0.0, 0.0, 4.0), square(1.0, 1.0, 1.0)]);
+        let svg = export_svg(&tree, ExportStyle::default());
+
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(r##"fill="#440154""##));
+        assert!(svg.contains(r##"fill="#fde725""##));
+
+        let style = ExportStyle {
+            fills: vec!["red".to_string()],
+            ..ExportStyle::default()
+        };
+        assert_eq!(export_svg(&tree, style).matches(r#"fill="red""#).count(), 2);
+
+        let polygons: Vec<Polygon> = import_to_lines(&svg, 0.0001)
+            .into_iter()
+            .map(|line| Polygon::new(line, Vec::new()))
+            .collect();
+        let areas: Vec<f64> = Tree::from_polygon(polygons)
+            .iter()
+            .map(|(_, polygon)| polygon.unsigned_area().round())
+            .collect();
+        assert_eq!(areas, vec![16.0, 1.0]);
+    }
+
+    #[test]
+    fn import_to_polygons_with_id_keeps_ids() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="0 0 10 10">
+            <path id="outer" d="M 0 0 L 10 0 L 10 10 L 0 10 Z"/>
+            <path d="M 1 1 L 2 1 L 2 2 L 1 2 Z"/>
+        </svg>"#;
+
+        let polygons = import_to_polygons_with_id(svg, 0.0001);
+        let ids: Vec<&str> = polygons.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["outer", "path1"]);
+
+        let tree = Tree::from_polygon_id(polygons);
+        let nested: Vec<(usize, &str)> = tree
+            .iter()
+            .map(|(depth, (id, _))| (depth, id.as_str()))
+            .collect();
+        assert_eq!(nested, vec![(0, "outer"), (1, "path1")]);
+    }
+
+    #[test]
+    fn import_to_lines_applies_transforms() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="0 0 10 10">
+            <g transform="translate(4 2)">
+                <path transform="scale(2)" d="M 0 0 L 1 0 L 1 1 L 0 1 Z"/>
+            </g>
+        </svg>"#;
+
+        let lines = import_to_lines(svg, 0.0001);
+        assert_eq!(lines.len(), 1);
+
+        let rect = geo::BoundingRect::bounding_rect(&lines[0]).unwrap();
+        assert_eq!(rect.min().x_y(), (4.0, -4.0));
+        assert_eq!(rect.max().x_y(), (6.0, -2.0));
+    }
+
+    #[test]
+    fn import_to_lines_without_view_box() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="2in" height="2in">
+            <path d="M 0 0 L 96 0 L 96 96 L 0 96 Z"/>
+        </svg>"#;
+
+        let lines = import_to_lines(svg, 0.0001);
+        let rect = geo::BoundingRect::bounding_rect(&lines[0]).unwrap();
+        assert_eq!(rect.min().x_y(), (0.0, -1.0));
+        assert_eq!(rect.max().x_y(), (1.0, 0.0));
+
+        let no_size = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <path d="M 0 0 L 96 0 L 96 96 L 0 96 Z"/>
+        </svg>"#;
+        let lines = import_to_lines(no_size, 0.0001);
+        assert!(
+            lines[0]
+                .coords()
+                .all(|c| c.x.is_finite() && c.y.is_finite())
+        );
+    }
+
+    #[test]
+    fn import_skips_invisible_paths() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="0 0 10 10">
+            <path d="M 0 0 L 1 0 L 1 1 Z"/>
+            <path fill="none" d="M 2 0 L 3 0 L 3 1 Z"/>
+            <path fill="none" stroke="black" d="M 4 0 L 5 0 L 5 1 Z"/>
+            <path fill-opacity="0" d="M 6 0 L 7 0 L 7 1 Z"/>
+            <path visibility="hidden" d="M 8 0 L 9 0 L 9 1 Z"/>
+            <g opacity="0"><path d="M 0 2 L 1 2 L 1 3 Z"/></g>
+        </svg>"#;
+
+        assert_eq!(import_to_lines(svg, 0.0001).len(), 6);
+
+        let options = ImportOptions {
+            skip_invisible: true,
+            ..ImportOptions::default()
+        };
+        assert_eq!(import_to_lines_with_options(svg, 0.0001, &options).len(), 2);
+    }
+
+    #[test]
+    fn import_to_lines_auto_scales_tolerance() {
+        let circle = |size: f64| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">
+                    <circle cx="{c}" cy="{c}" r="{c}"/>
+                    <rect width="{size}" height="{size}" fill="none" stroke="black"/>
+                </svg>"#,
+                size = size,
+                c = size / 2.0,
+            )
+        };
+
+        for size in [0.01, 10000.0] {
+            let lines = import_to_lines_auto(&circle(size), 64);
+            assert_eq!(lines.len(), 2);
+
+            let points = lines[0].0.len();
+            assert!(
+                (32..=128).contains(&points),
+                "{} points at size {}",
+                points,
+                size
+            );
+            assert_eq!(lines[1].0.len(), 5);
+        }
+    }
+
+    #[test]
+    fn import_many_matches_import_to_lines() {
+        let svgs = [
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="96" height="96" viewBox="0 0 96 96">
+                <rect x="0" y="0" width="10" height="10"/>
+            </svg>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="96" height="96" viewBox="0 0 96 96">
+                <rect x="0" y="0" width="10" height="10"/>
+                <circle cx="50" cy="50" r="20"/>
+            </svg>"#,
+        ];
+
+        let options = usvg_options();
+        let batch = import_many(svgs.iter().map(|svg| svg.to_string()), 0.01, &options).unwrap();
+        let single: Vec<Vec<geo::LineString>> =
+            svgs.iter().map(|svg| import_to_lines(svg, 0.01)).collect();
+        assert_eq!(batch, single);
+        assert_eq!(batch[1].len(), 2);
+
+        let broken = [svgs[0].to_string(), "<svg".to_string()];
+        assert!(import_many(broken.into_iter(), 0.01, &options).is_err());
+    }
+
+    #[test]
+    fn import_outlines_strokes() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="0 0 10 10">
+            <rect x="2" y="2" width="6" height="6" fill="none" stroke="black" stroke-width="2"/>
+            <rect x="4" y="4" width="2" height="2"/>
+            <path d="M 0 9.5 L 10 9.5" fill="none" stroke="black" stroke-width="1"/>
+        </svg>"#;
+
+        let areas = |lines: Vec<geo::LineString>| -> Vec<f64> {
+            lines
+                .into_iter()
+                .map(|line| Polygon::new(line, Vec::new()).unsigned_area())
+                .collect()
+        };
+        assert_eq!(areas(import_to_lines(svg, 0.0001)), vec![36.0, 4.0, 0.0]);
+
+        let options = ImportOptions {
+            outline_strokes: true,
+            ..ImportOptions::default()
+        };
+        let lines = import_to_lines_with_options(svg, 0.0001, &options);
+        assert_eq!(areas(lines.clone()), vec![64.0, 16.0, 4.0, 10.0]);
+
+        let tree = Tree::from_polygon(
+            lines
+                .into_iter()
+                .map(|line| Polygon::new(line, Vec::new()))
+                .collect(),
+        );
+        assert_eq!(tree.depth_at_point([1.5, -5.0]), 1);
+        assert_eq!(tree.depth_at_point([3.5, -5.0]), 2);
+        assert_eq!(tree.depth_at_point([5.0, -5.0]), 3);
+        assert_eq!(tree.depth_at_point([5.0, -9.5]), 1);
+    }
+
+    #[test]
+    fn import_outlines_sharp_and_degenerate_strokes() {
+        use geo::Validation;
+
+        // A hairpin turn, a tight curve much narrower than the stroke, and
+        // a path that repeats a point.
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="0 0 10 10">
+            <path d="M 1 1 L 9 1.5 L 1 2" fill="none" stroke="black" stroke-width="0.5"/>
+            <path d="M 2 5 C 8 4 8 6 2 5" fill="none" stroke="black" stroke-width="1"/>
+            <path d="M 1 8 L 1 8 L 5 8 L 5 8 L 9 8" fill="none" stroke="black" stroke-width="1"/>
+        </svg>"#;
+        let options = ImportOptions {
+            outline_strokes: true,
+            ..ImportOptions::default()
+        };
+
+        let lines = import_to_lines_with_options(svg, 0.001, &options);
+        assert!(!lines.is_empty());
+        for line in &lines {
+            assert!(line.0.iter().all(|c| c.x.is_finite() && c.y.is_finite()));
+            assert!(Polygon::new(line.clone(), Vec::new()).is_valid());
+        }
+
+        let tree = Tree::from_polygon(
+            lines
+                .into_iter()
+                .map(|line| Polygon::new(line, Vec::new()))
+                .collect(),
+        );
+        assert_eq!(tree.depth_at_point([5.0, -1.25]), 1);
+        assert_eq!(tree.depth_at_point([3.0, -8.0]), 1);
+        assert_eq!(tree.depth_at_point([3.0, -8.6]), 0);
+        // The miter at the hairpin is cut off at the miter limit.
+        assert_eq!(tree.depth_at_point([9.9, -1.5]), 0);
+    }
+
+    #[test]
+    fn import_keeps_precision_far_from_origin() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10in" height="10in" viewBox="100000000 100000000 10 10">
+            <rect x="100000001.25" y="100000002.5" width="1.5" height="0.75"/>
+            <rect x="100000001.5" y="100000002.75" width="0.5" height="0.25"/>
+        </svg>"#;
+
+        let lines = import_to_lines(svg, 0.0001);
+        let rect = geo::BoundingRect::bounding_rect(&lines[0]).unwrap();
+        assert_eq!(rect.min().x_y(), (100000001.25, -100000003.25));
+        assert_eq!(rect.max().x_y(), (100000002.75, -100000002.5));
+
+        let tree = Tree::from_polygon(
+            lines
+                .into_iter()
+                .map(|line| Polygon::new(line, Vec::new()))
+                .collect(),
+        );
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by the unit tests of the geo-backed modules.
+
+use geo::Polygon;
+
+/// Axis-aligned square with its lower left corner at `(x, y)`.
+pub(crate) fn square(x: f64, y: f64, size: f64) -> Polygon {
+    Polygon::new(
+        geo::LineString::from(vec![
+            (x, y),
+            (x + size, y),
+            (x + size, y + size),
+            (x, y + size),
+            (x, y),
+        ]),
+        Vec::new(),
+    )
+}
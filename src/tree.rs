@@ -1,410 +1,5209 @@
-use std::collections::VecDeque;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
-use rstar::{AABB, PointDistance, RTree, RTreeObject};
+use rstar::{AABB, Envelope, PointDistance, RTree, RTreeObject, SelectionFunction};
 
 pub trait Shape {
-    fn contains_shape(&self, rhs: &Self) -> bool;
     fn contains_point(&self, point: [f32; 2]) -> bool;
     fn bounding_rect(&self) -> ([f32; 2], [f32; 2]); // [min.x, min.y, max.x, max.y]
     fn center_point(&self) -> [f32; 2];
     fn area(&self) -> f32;
-}
 
-pub type AABBType = [f32; 2];
+    /// Approximate containment: `rhs`'s bounding rect lies inside this one's
+    /// and this shape contains each of `rhs`'s
+    /// [`Shape::interior_sample_points`]. Shapes with exact geometry should
+    /// override it.
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        let (l_min, l_max) = self.bounding_rect();
+        let (r_min, r_max) = rhs.bounding_rect();
+        l_min[0] <= r_min[0]
+            && l_min[1] <= r_min[1]
+            && r_max[0] <= l_max[0]
+            && r_max[1] <= l_max[1]
+            && rhs
+                .interior_sample_points(CONTAINMENT_SAMPLES)
+                .into_iter()
+                .all(|point| self.contains_point(point))
+    }
 
-#[derive(Debug, Clone)]
-pub struct Tree<T>
-where
-    T: Shape + Clone,
-{
-    root: Option<TreeNode<T>>,
+    /// Up to `n` points inside the shape, spread over it so that a container
+    /// holding all of them most likely holds the whole shape. Defaults to
+    /// the center point alone.
+    fn interior_sample_points(&self, _n: usize) -> Vec<[f32; 2]> {
+        vec![self.center_point()]
+    }
+
+    fn perimeter(&self) -> f32 {
+        let (min, max) = self.bounding_rect();
+        2.0 * ((max[0] - min[0]) + (max[1] - min[1]))
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        let (l_min, l_max) = self.bounding_rect();
+        let (r_min, r_max) = other.bounding_rect();
+        l_min[0] <= r_max[0] && r_min[0] <= l_max[0] && l_min[1] <= r_max[1] && r_min[1] <= l_max[1]
+    }
+
+    /// Distance from `point` to the shape, 0 inside it. Defaults to the
+    /// distance to the bounding rect, which is exact for rectangles and a
+    /// lower bound for anything else; overriding it must keep that bound.
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        let (min, max) = self.bounding_rect();
+        crate::float::sqrt(AABB::from_corners(min, max).distance_2(&point))
+    }
+
+    /// Whether the shape has no geometry at all, so no meaningful bounding
+    /// rect or center. Trees skip empty values on every insertion path.
+    /// Defaults to `false`.
+    fn is_empty(&self) -> bool {
+        false
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct TreeNode<T>
-where
-    T: Shape + Clone,
-{
-    value: T,
-    bounding_rect: AABB<AABBType>,
-    center_point: [f32; 2],
-    children: RTree<TreeNode<T>>,
-    area: f32,
+/// Shapes that can be mapped through an affine transform, as
+/// [`Tree::transform`] and [`Tree::relocate_at_point`] require.
+pub trait Transform {
+    /// This shape mapped through the affine `matrix` `[a, b, c, d, e, f]`,
+    /// which takes `(x, y)` to `(a * x + c * y + e, b * x + d * y + f)` as in
+    /// SVG.
+    fn transform(&self, matrix: [f32; 6]) -> Self;
 }
 
-// MARK: Tree
+/// How many of a shape's [`Shape::interior_sample_points`] containment
+/// tests check.
+const CONTAINMENT_SAMPLES: usize = 9;
 
-impl<T> Tree<T>
+/// Whether `container` holds `shape` with `tolerance` of slack. Beyond
+/// [`Shape::contains_shape`], a positive tolerance also accepts a shape no
+/// larger than the container whose sample points all lie within
+/// `tolerance` of it.
+fn contains_within<T: Shape>(container: &T, shape: &T, tolerance: f32) -> bool {
+    container.contains_shape(shape)
+        || tolerance > 0.0
+            && container.area() >= shape.area()
+            && shape
+                .interior_sample_points(CONTAINMENT_SAMPLES)
+                .into_iter()
+                .all(|point| container.distance_to_point(point) <= tolerance)
+}
+
+impl<T> Shape for Arc<T>
 where
-    T: Shape + Clone,
+    T: Shape,
 {
-    pub fn iter(&self) -> TreeNodeDepthIterator<T> {
-        if let Some(root) = &self.root {
-            root.iter()
-        } else {
-            TreeNodeDepthIterator {
-                order: Default::default(),
-            }
-        }
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        (**self).contains_shape(rhs)
     }
 
-    pub fn root(&self) -> &Option<TreeNode<T>> {
-        &self.root
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        (**self).contains_point(point)
     }
-}
 
-impl<T> From<Vec<T>> for Tree<T>
-where
-    T: Shape + Clone + Default,
-{
-    fn from(value: Vec<T>) -> Self {
-        Self::from((value, Default::default()))
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        (**self).bounding_rect()
     }
-}
 
-impl<T> From<(Vec<T>, T)> for Tree<T>
-where
-    T: Shape + Clone,
-{
-    fn from(value: (Vec<T>, T)) -> Self {
-        let mut root = TreeNode {
-            value: value.1,
-            bounding_rect: AABB::from_corners([0.0, 0.0], [0.0, 0.0]),
-            center_point: [0.0, 0.0],
-            children: Default::default(),
-            area: 0.0,
-        };
-        for x in value.0 {
-            root.add_node(x);
-        }
+    fn center_point(&self) -> [f32; 2] {
+        (**self).center_point()
+    }
+
+    fn area(&self) -> f32 {
+        (**self).area()
+    }
+
+    fn perimeter(&self) -> f32 {
+        (**self).perimeter()
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        (**self).intersects(other)
+    }
+
+    fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+        (**self).interior_sample_points(n)
+    }
+
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        (**self).distance_to_point(point)
+    }
 
-        Self { root: Some(root) }
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
     }
 }
 
-impl<T> FromIterator<T> for Tree<T>
+impl<T> Shape for Box<T>
 where
-    T: Shape + Clone + Default,
+    T: Shape,
 {
-    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Tree<T> {
-        let mut arr: Vec<TreeNode<T>> = iter.into_iter().map(|elem| elem.into()).collect();
-        arr.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap());
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        (**self).contains_shape(rhs)
+    }
 
-        let mut root: TreeNode<T> = TreeNode::from(T::default());
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        (**self).contains_point(point)
+    }
 
-        for shape in arr {
-            root.add_node(shape);
-        }
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        (**self).bounding_rect()
+    }
 
-        Tree { root: Some(root) }
+    fn center_point(&self) -> [f32; 2] {
+        (**self).center_point()
     }
-}
 
-// MARK: TreeNode
+    fn area(&self) -> f32 {
+        (**self).area()
+    }
 
-impl<T> RTreeObject for TreeNode<T>
-where
-    T: Shape + Clone,
-{
-    type Envelope = AABB<AABBType>;
+    fn perimeter(&self) -> f32 {
+        (**self).perimeter()
+    }
 
-    fn envelope(&self) -> Self::Envelope {
-        self.bounding_rect.clone()
+    fn intersects(&self, other: &Self) -> bool {
+        (**self).intersects(other)
     }
-}
 
-impl<T> PointDistance for TreeNode<T>
-where
-    T: Shape + Clone,
-{
-    fn distance_2(
-        &self,
-        point: &<Self::Envelope as rstar::Envelope>::Point,
-    ) -> <<Self::Envelope as rstar::Envelope>::Point as rstar::Point>::Scalar {
-        if self.value.contains_point(*point) {
-            0.0
-        } else {
-            self.center_point.distance_2(point)
-        }
+    fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+        (**self).interior_sample_points(n)
+    }
+
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        (**self).distance_to_point(point)
+    }
+
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
     }
 }
 
-impl<T> From<T> for TreeNode<T>
+impl<T> Shape for &T
 where
-    T: Shape + Clone,
+    T: Shape,
 {
-    fn from(value: T) -> Self {
-        let r = value.bounding_rect();
-        let center_point = value.center_point();
-        let area = value.area();
-        Self {
-            value,
-            bounding_rect: AABB::from_corners(r.0, r.1),
-            center_point,
-            children: RTree::new(),
-            area,
-        }
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        (**self).contains_shape(rhs)
+    }
+
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        (**self).contains_point(point)
+    }
+
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        (**self).bounding_rect()
+    }
+
+    fn center_point(&self) -> [f32; 2] {
+        (**self).center_point()
+    }
+
+    fn area(&self) -> f32 {
+        (**self).area()
+    }
+
+    fn perimeter(&self) -> f32 {
+        (**self).perimeter()
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        (**self).intersects(other)
+    }
+
+    fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+        (**self).interior_sample_points(n)
+    }
+
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        (**self).distance_to_point(point)
+    }
+
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
     }
 }
 
-impl<T> TreeNode<T>
+/// A shape tagged with data, such as an id. Everything is forwarded to the
+/// shape; the tag is only carried along.
+impl<A, B> Shape for (A, B)
 where
-    T: Shape + Clone,
+    A: Clone,
+    B: Shape,
 {
-    pub fn add_node<E>(&mut self, elem: E)
-    where
-        E: Into<TreeNode<T>>,
-    {
-        let elem = elem.into();
+    fn contains_shape(&self, rhs: &Self) -> bool {
+        self.1.contains_shape(&rhs.1)
+    }
 
-        for child in &mut self.children {
-            if child.add_node_tree_node(&elem) {
-                return;
-            }
-        }
+    fn contains_point(&self, point: [f32; 2]) -> bool {
+        self.1.contains_point(point)
+    }
 
-        self.children.insert(elem.clone());
+    fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+        self.1.bounding_rect()
     }
 
-    fn add_node_tree_node(&mut self, elem: &TreeNode<T>) -> bool {
-        if self.value.contains_shape(&elem.value) {
-            for child in self.children.locate_all_at_point_mut(&elem.center_point) {
-                if child.add_node_tree_node(elem) {
-                    return true;
-                }
-            }
+    fn center_point(&self) -> [f32; 2] {
+        self.1.center_point()
+    }
 
-            self.children.insert(elem.clone());
-            return true;
-        }
-        false
+    fn area(&self) -> f32 {
+        self.1.area()
     }
 
-    fn iter(&self) -> TreeNodeDepthIterator<T> {
-        let mut queue = VecDeque::new();
-        for child in &self.children {
-            queue.push_back((0, child));
-        }
-        TreeNodeDepthIterator { order: queue }
+    fn perimeter(&self) -> f32 {
+        self.1.perimeter()
     }
 
-    pub fn value(&self) -> &T {
-        &self.value
+    fn intersects(&self, other: &Self) -> bool {
+        self.1.intersects(&other.1)
     }
 
-    pub fn children<'a>(&'a self) -> Vec<&'a TreeNode<T>> {
-        let mut children = Vec::new();
+    fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+        self.1.interior_sample_points(n)
+    }
 
-        for child in &self.children {
-            children.push(child);
-        }
+    fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+        self.1.distance_to_point(point)
+    }
 
-        return children;
+    fn is_empty(&self) -> bool {
+        self.1.is_empty()
     }
 }
 
-// MARK: Iterator
-
-#[derive(Debug, Clone)]
-pub struct TreeNodeDepthIterator<'a, T>
+impl<T> Transform for Arc<T>
 where
-    T: Shape + Clone,
+    T: Transform,
 {
-    order: VecDeque<(usize, &'a TreeNode<T>)>,
+    fn transform(&self, matrix: [f32; 6]) -> Self {
+        Arc::new(T::transform(self, matrix))
+    }
 }
 
-impl<'a, T> Iterator for TreeNodeDepthIterator<'a, T>
+impl<T> Transform for Box<T>
 where
-    T: Shape + Clone,
+    T: Transform,
 {
-    type Item = (usize, &'a T);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some((depth, tree_node)) = self.order.pop_front() else {
-            return None;
-        };
-
-        for child in &tree_node.children {
-            self.order.push_back((depth + 1, child));
-        }
+    fn transform(&self, matrix: [f32; 6]) -> Self {
+        Box::new(T::transform(self, matrix))
+    }
+}
 
-        Some((depth, &tree_node.value))
+impl<A, B> Transform for (A, B)
+where
+    A: Clone,
+    B: Transform,
+{
+    fn transform(&self, matrix: [f32; 6]) -> Self {
+        (self.0.clone(), self.1.transform(matrix))
     }
 }
 
+/// Callbacks for [`Tree::visit`]. `enter` is called for a node before any
+/// of its descendants and `leave` after all of them.
+pub trait TreeVisitor<T> {
+    fn enter(&mut self, _depth: usize, _value: &T) {}
+    fn leave(&mut self, _depth: usize, _value: &T) {}
+}
+
+pub type AABBType = [f32; 2];
+
+/// Shapes nested by containment. `Tree<T>` holds no shared or interior
+/// mutable state, so it is `Send` and `Sync` whenever `T` is and can be put
+/// behind an `Arc` for concurrent read-only queries.
+///
+/// Every tree has a root node above its top-level shapes. Its value is only
+/// reachable through [`Tree::root`]: iteration, traversal and point queries
+/// start at its children, which are at depth 0. Most constructors give it a
+/// placeholder value with zeroed geometry; [`Tree::with_root`] keeps a real
+/// shape there, which [`Tree::root_contains_point`] can then test.
+///
+/// The root accepts every shape whatever its own geometry: `contains_shape`
+/// is never called on the root's value, so a shape the root does not
+/// contain still becomes a top-level node rather than being rejected.
+/// Compare shapes against `root().value()` first to keep them out.
 #[derive(Debug, Clone)]
-pub struct TreeNodeDepthIntoIterator<T>
+pub struct Tree<T>
 where
     T: Shape + Clone,
 {
-    order: VecDeque<(usize, TreeNode<T>)>,
+    root: Option<TreeNode<T>>,
+    /// Room reserved for the top-level nodes when [`Extend`] bulk-loads
+    /// into an empty tree. See [`Tree::with_capacity`].
+    capacity: usize,
 }
 
-impl<T> Iterator for TreeNodeDepthIntoIterator<T>
+#[derive(Debug, Clone)]
+pub struct TreeNode<T>
 where
     T: Shape + Clone,
 {
-    type Item = (usize, T);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some((depth, tree_node)) = self.order.pop_front() else {
-            return None;
-        };
-
-        for child in tree_node.children {
-            self.order.push_back((depth + 1, child));
-        }
-
-        Some((depth, tree_node.value))
-    }
+    value: T,
+    bounding_rect: AABB<AABBType>,
+    center_point: [f32; 2],
+    children: RTree<TreeNode<T>>,
+    area: f32,
+    subtree_bounds: AABB<AABBType>,
 }
 
-impl<T> IntoIterator for Tree<T>
+// MARK: Tree
+
+impl<T> Tree<T>
 where
     T: Shape + Clone,
 {
-    type Item = (usize, T);
+    /// Builds a tree like `Tree::from((values, root))`, sorting by descending
+    /// area first and resolving overlapping candidate parents with `policy`.
+    pub fn from_with_policy(values: Vec<T>, root: T, policy: &ContainmentPolicy<T>) -> Self {
+        TreeBuilder::new(root).policy(policy.clone()).build(values)
+    }
 
-    type IntoIter = TreeNodeDepthIntoIterator<T>;
+    /// Breadth-first iterator over `(depth, value)` pairs. Same as
+    /// [`Tree::iter_bfs`]; use [`Tree::iter_dfs`] for depth-first order.
+    pub fn iter(&self) -> TreeNodeDepthIterator<'_, T> {
+        self.iter_bfs()
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        if let Some(root) = self.root {
-            let mut queue = VecDeque::new();
-            for child in root.children {
-                queue.push_back((0, child));
-            }
-            TreeNodeDepthIntoIterator { order: queue }
+    /// Every top-level node, then every node at depth 1, and so on.
+    pub fn iter_bfs(&self) -> TreeNodeDepthIterator<'_, T> {
+        if let Some(root) = &self.root {
+            root.iter()
         } else {
-            TreeNodeDepthIntoIterator {
+            TreeNodeDepthIterator {
                 order: Default::default(),
             }
         }
     }
-}
 
-#[cfg(feature = "geo-integration")]
-mod geo_impls {
-    use crate::*;
-    use geo::{Area, Contains, InteriorPoint, Polygon};
-
-    impl Shape for Polygon {
-        fn contains_shape(&self, rhs: &Self) -> bool {
-            if self.unsigned_area() < rhs.unsigned_area() {
-                return false;
-            }
+    /// Pre-order depth-first iterator: each node is followed by its whole
+    /// subtree before its next sibling.
+    pub fn iter_dfs(&self) -> TreeNodeDfsIterator<'_, T> {
+        let mut iter = TreeNodeDfsIterator { stack: Vec::new() };
+        if let Some(root) = &self.root {
+            iter.push_children(0, root);
+        }
+        iter
+    }
 
-            if let Some(point) = rhs.interior_point() {
-                self.contains(&point)
-            } else {
-                false
-            }
+    /// Post-order depth-first iterator: each node comes after its whole
+    /// subtree, so leaves come first and every container follows all the
+    /// shapes inside it. Siblings are visited by ascending area, then center
+    /// point, as in [`TreeNode::children_sorted_by_area`], so the order is
+    /// the same on every build.
+    pub fn iter_leaves_postorder(&self) -> impl Iterator<Item = (usize, &T)> {
+        // Nodes still to visit, flagged once their children are pushed.
+        let mut stack: Vec<(usize, &TreeNode<T>, bool)> = Vec::new();
+        if let Some(root) = &self.root {
+            stack.extend(
+                root.children_sorted_by_area()
+                    .into_iter()
+                    .rev()
+                    .map(|child| (0, child, false)),
+            );
         }
 
-        fn contains_point(&self, point: [f32; 2]) -> bool {
-            self.contains(&geo::coord! {
-                x: point[0] as f64,
-                y: point[1] as f64,
-            })
+        core::iter::from_fn(move || {
+            while let Some((depth, node, expanded)) = stack.pop() {
+                if expanded || node.children.size() == 0 {
+                    return Some((depth, &node.value));
+                }
+                stack.push((depth, node, true));
+                stack.extend(
+                    node.children_sorted_by_area()
+                        .into_iter()
+                        .rev()
+                        .map(|child| (depth + 1, child, false)),
+                );
+            }
+            None
+        })
+    }
+
+    /// Like [`Tree::iter`], but skips every node whose cached area is below
+    /// `min_area` without descending into it. A child never has more area
+    /// than its parent, so nothing large enough is missed.
+    pub fn iter_min_area(&self, min_area: f32) -> impl Iterator<Item = (usize, &T)> {
+        let large = move |node: &TreeNode<T>| node.area >= min_area;
+        let mut order: VecDeque<(usize, &TreeNode<T>)> = VecDeque::new();
+        if let Some(root) = &self.root {
+            order.extend(
+                root.children
+                    .iter()
+                    .filter(|child| large(child))
+                    .map(|child| (0, child)),
+            );
         }
 
-        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
-            if let Some(rect) = geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self) {
-                let ((x1, y1), (x2, y2)) = (rect.min().x_y(), rect.max().x_y());
-                ([x1 as f32, y1 as f32], [x2 as f32, y2 as f32])
-            } else {
-                panic!("Could not get bounding rect.");
+        core::iter::from_fn(move || {
+            let (depth, node) = order.pop_front()?;
+            order.extend(
+                node.children
+                    .iter()
+                    .filter(|child| large(child))
+                    .map(|child| (depth + 1, child)),
+            );
+            Some((depth, &node.value))
+        })
+    }
+
+    /// Like [`Tree::iter`], restricted to nodes whose depth is in `range`.
+    /// Shallower nodes are passed through to reach the band, and nothing
+    /// below its upper end is visited.
+    pub fn iter_depth_range(
+        &self,
+        range: core::ops::RangeInclusive<usize>,
+    ) -> impl Iterator<Item = (usize, &T)> {
+        let mut order: VecDeque<(usize, &TreeNode<T>)> = VecDeque::new();
+        if let Some(root) = &self.root {
+            order.extend(root.children.iter().map(|child| (0, child)));
+        }
+
+        core::iter::from_fn(move || {
+            while let Some((depth, node)) = order.pop_front() {
+                if depth < *range.end() {
+                    order.extend(node.children.iter().map(|child| (depth + 1, child)));
+                }
+                if range.contains(&depth) {
+                    return Some((depth, &node.value));
+                }
+            }
+            None
+        })
+    }
+
+    /// Like [`Tree::iter`], but also yields the parent's value, or `None`
+    /// for top-level nodes.
+    pub fn iter_with_parent(&self) -> TreeNodeParentIterator<'_, T> {
+        let mut order = VecDeque::new();
+        if let Some(root) = &self.root {
+            for child in &root.children {
+                order.push_back((0, None, child));
             }
         }
+        TreeNodeParentIterator { order }
+    }
 
-        fn center_point(&self) -> [f32; 2] {
-            if let Some(point) = self.interior_point() {
-                let (x, y) = point.x_y();
-                [x as f32, y as f32]
-            } else {
-                panic!("Could not get center point.");
+    /// Depth-first traversal calling `visitor.enter` on the way down and
+    /// `visitor.leave` on the way back up.
+    pub fn visit<V: TreeVisitor<T>>(&self, visitor: &mut V) {
+        if let Some(root) = &self.root {
+            for child in &root.children {
+                child.visit(0, visitor);
             }
         }
+    }
 
-        fn area(&self) -> f32 {
-            self.unsigned_area() as f32
+    /// Values grouped by depth: `levels()[d]` holds every value at depth `d`,
+    /// in [`Tree::iter`] order. Built in one breadth-first pass.
+    pub fn levels(&self) -> Vec<Vec<&T>> {
+        let mut levels: Vec<Vec<&T>> = Vec::new();
+        for (depth, value) in self.iter() {
+            if depth == levels.len() {
+                levels.push(Vec::new());
+            }
+            levels[depth].push(value);
         }
+        levels
     }
 
-    impl Tree<Polygon> {
-        pub fn from_polygon(mut value: Vec<Polygon>) -> Self {
-            value.sort_by(|l, r| r.unsigned_area().partial_cmp(&l.unsigned_area()).unwrap());
-            Self::from((
-                value,
-                Polygon::new(geo::LineString::new(Vec::new()), Vec::new()),
-            ))
+    /// Owned values grouped by depth, each paired with the index of its
+    /// parent in the level above (0 for the top level, whose parent is the
+    /// root). [`Tree::from_levels`] rebuilds the same tree from it.
+    pub fn to_levels(&self) -> Vec<Vec<(usize, T)>> {
+        let mut levels = Vec::new();
+        let mut parents: Vec<&TreeNode<T>> = self.root.iter().collect();
+        while !parents.is_empty() {
+            let mut level = Vec::new();
+            let mut nodes = Vec::new();
+            for (index, parent) in parents.iter().enumerate() {
+                for child in &parent.children {
+                    level.push((index, child.value.clone()));
+                    nodes.push(child);
+                }
+            }
+            if !level.is_empty() {
+                levels.push(level);
+            }
+            parents = nodes;
         }
+        levels
     }
 
-    impl<T> Shape for (T, Polygon) {
-        fn contains_shape(&self, rhs: &Self) -> bool {
-            self.1.contains_shape(&rhs.1)
+    /// Builds a tree from levels laid out as by [`Tree::to_levels`],
+    /// trusting the given nesting instead of testing containment, which
+    /// makes this the fast path for loading a stored tree. Debug builds
+    /// still assert that every parent contains its children.
+    ///
+    /// # Panics
+    ///
+    /// If a parent index is out of range for the level above.
+    pub fn from_levels(root: T, mut levels: Vec<Vec<(usize, T)>>) -> Self {
+        // Children of each node in the level being built, by position.
+        let mut below: Vec<Vec<TreeNode<T>>> = Vec::new();
+        while let Some(level) = levels.pop() {
+            let parents = levels.last();
+            let mut grouped: Vec<Vec<TreeNode<T>>> = (0..parents.map_or(1, Vec::len))
+                .map(|_| Vec::new())
+                .collect();
+
+            let mut children = below.into_iter();
+            for (parent, value) in level {
+                let parent = if parents.is_some() { parent } else { 0 };
+                debug_assert!(
+                    parents.is_none_or(|parents| parents[parent].1.contains_shape(&value)),
+                    "from_levels: node is not contained by its parent"
+                );
+                let node = TreeNode::with_children(value, children.next().unwrap_or_default());
+                grouped[parent].push(node);
+            }
+            below = grouped;
         }
 
-        fn contains_point(&self, point: [f32; 2]) -> bool {
-            self.1.contains_point(point)
+        Self::from_top_level(root, below.pop().unwrap_or_default())
+    }
+
+    /// Pre-order traversal calling `f` with each node's index path and value.
+    /// Siblings are numbered as in [`Tree::get_by_path`], by ascending area
+    /// then center point, so the paths are the same on every run and can be
+    /// passed back to `get_by_path`.
+    pub fn for_each_with_path(&self, mut f: impl FnMut(&[usize], &T)) {
+        if let Some(root) = &self.root {
+            root.for_each_with_path(&mut Vec::new(), &mut f);
         }
+    }
 
-        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
-            self.1.bounding_rect()
+    /// Owned snapshot of the tree as `(depth, value)` pairs, in the same
+    /// breadth-first order as [`Tree::iter`].
+    pub fn to_vec(&self) -> Vec<(usize, T)> {
+        self.iter()
+            .map(|(depth, value)| (depth, value.clone()))
+            .collect()
+    }
+
+    /// Post-order aggregation over the hierarchy. Each node starts with
+    /// `init(value)` and then folds in the accumulators of its children with
+    /// `combine`. The result mirrors the tree: one [`FoldedNode`] per
+    /// top-level shape, each holding its children's folded nodes.
+    pub fn fold_bottom_up<A, I, F>(&self, init: I, combine: F) -> Vec<FoldedNode<'_, T, A>>
+    where
+        I: Fn(&T) -> A,
+        F: Fn(A, &A) -> A,
+    {
+        if let Some(root) = &self.root {
+            root.children
+                .iter()
+                .map(|child| child.fold_bottom_up(&init, &combine))
+                .collect()
+        } else {
+            Vec::new()
         }
+    }
 
-        fn center_point(&self) -> [f32; 2] {
-            self.1.center_point()
+    /// Value of the node `shape` would be nested under if it were inserted
+    /// now, or `None` if it would become a top-level node. Uses the same
+    /// descent as insertion without modifying the tree.
+    pub fn would_parent(&self, shape: &T) -> Option<&T> {
+        let root = self.root.as_ref()?;
+        let center_point = shape.center_point();
+
+        root.children
+            .iter()
+            .find_map(|child| child.would_parent(shape, &center_point))
+    }
+
+    /// Every value whose shape contains `shape`, innermost (smallest area)
+    /// first. Only containers are descended into.
+    pub fn enclosing(&self, shape: &T) -> Vec<&T> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        let (min, max) = shape.bounding_rect();
+        let envelope = AABB::from_corners(min, max);
+        let mut found = Vec::new();
+        root.enclosing(shape, &envelope, &mut found);
+
+        found.sort_by(|l, r| l.area.total_cmp(&r.area));
+        found.into_iter().map(|node| &node.value).collect()
+    }
+
+    /// Every value that `shape` contains, in breadth-first order. Subtrees
+    /// whose bounding rect misses the shape's are skipped.
+    pub fn contained_within(&self, shape: &T) -> Vec<&T> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        let (min, max) = shape.bounding_rect();
+        let envelope = AABB::from_corners(min, max);
+        let mut found = Vec::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            for child in node.children.locate_in_envelope_intersecting(&envelope) {
+                if envelope.contains_envelope(&child.bounding_rect)
+                    && shape.contains_shape(&child.value)
+                {
+                    found.push(&child.value);
+                }
+                queue.push_back(child);
+            }
         }
+        found
+    }
 
-        fn area(&self) -> f32 {
-            self.1.area()
+    /// Differences between this tree and `other`, matching nodes by `key`.
+    /// A node counts as moved when its parent's key differs between the two.
+    #[cfg(feature = "std")]
+    pub fn diff_by_key<'a, K, F>(&'a self, other: &'a Self, key: F) -> TreeDiff<'a, T>
+    where
+        K: Eq + Hash,
+        F: Fn(&'a T) -> K,
+    {
+        let parents = |tree: &'a Self| -> HashMap<K, (Option<K>, &'a T)> {
+            tree.iter_with_parent()
+                .map(|(_, parent, value)| (key(value), (parent.map(&key), value)))
+                .collect()
+        };
+        let (before, after) = (parents(self), parents(other));
+
+        let mut diff = TreeDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            moved: Vec::new(),
+        };
+        for (_, _, value) in self.iter_with_parent() {
+            if !after.contains_key(&key(value)) {
+                diff.removed.push(value);
+            }
+        }
+        for (_, parent, value) in other.iter_with_parent() {
+            match before.get(&key(value)) {
+                None => diff.added.push(value),
+                Some((old_parent, old_value)) if *old_parent != parent.map(&key) => {
+                    diff.moved.push((old_value, value));
+                }
+                Some(_) => {}
+            }
         }
+        diff
     }
 
-    impl<T: Clone + Default> Tree<(T, Polygon)> {
-        pub fn from_polygon_id(mut value: Vec<(T, Polygon)>) -> Self {
-            value.sort_by(|l, r| {
-                r.1.unsigned_area()
-                    .partial_cmp(&l.1.unsigned_area())
-                    .unwrap()
+    /// Value reached by following `path` from the root, one child index per
+    /// level. Siblings are indexed by ascending area, then center point (the
+    /// order used by `PartialEq`), so a path stays valid for any tree built
+    /// from the same shapes. An empty path or out-of-range index gives `None`.
+    pub fn get_by_path(&self, path: &[usize]) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        for &index in path {
+            node = *node.children_sorted_by_area().get(index)?;
+        }
+
+        if path.is_empty() {
+            None
+        } else {
+            Some(&node.value)
+        }
+    }
+
+    /// Every node at every depth bulk-loaded into one flat R-tree, for plain
+    /// spatial queries that don't need the hierarchy. The nodes in it have no
+    /// children.
+    pub fn to_rtree(&self) -> RTree<TreeNode<T>> {
+        let mut nodes = Vec::new();
+        let mut queue: VecDeque<&TreeNode<T>> = self.root.iter().collect();
+        while let Some(node) = queue.pop_front() {
+            for child in &node.children {
+                nodes.push(TreeNode {
+                    value: child.value.clone(),
+                    bounding_rect: child.bounding_rect,
+                    center_point: child.center_point,
+                    children: RTree::new(),
+                    area: child.area,
+                    subtree_bounds: child.bounding_rect,
+                });
+                queue.push_back(child);
+            }
+        }
+        RTree::bulk_load(nodes)
+    }
+
+    /// Pairs of nodes that share a parent and intersect each other.
+    pub fn find_overlapping_siblings(&self) -> Vec<(&T, &T)> {
+        let mut overlapping = Vec::new();
+        if let Some(root) = &self.root {
+            root.find_overlapping_siblings(&mut overlapping);
+        }
+        overlapping
+    }
+
+    /// Union of every node's bounding rect, or `None` for an empty tree.
+    pub fn bounding_rect(&self) -> Option<([f32; 2], [f32; 2])> {
+        let root = self.root.as_ref()?;
+        if root.children.size() == 0 {
+            return None;
+        }
+
+        Some(root.bounding_rect_recursive())
+    }
+
+    /// Smallest and largest cached node area, found in one traversal, or
+    /// `None` for an empty tree.
+    pub fn area_bounds(&self) -> Option<(f32, f32)> {
+        let mut bounds: Option<(f32, f32)> = None;
+        let mut stack: Vec<&TreeNode<T>> =
+            self.root.iter().flat_map(|root| &root.children).collect();
+        while let Some(node) = stack.pop() {
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(node.area), max.max(node.area)),
+                None => (node.area, node.area),
             });
-            Self::from((
-                value,
-                (
-                    T::default(),
-                    Polygon::new(geo::LineString::new(Vec::new()), Vec::new()),
-                ),
-            ))
+            stack.extend(&node.children);
         }
+        bounds
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::path::Path;
+    pub fn min_area(&self) -> Option<f32> {
+        self.area_bounds().map(|(min, _)| min)
+    }
 
-    use geo::Polygon;
+    pub fn max_area(&self) -> Option<f32> {
+        self.area_bounds().map(|(_, max)| max)
+    }
 
-    use crate::*;
+    /// Whether every node's cached bounding rect lies within its parent's.
+    /// Only the cached envelopes are compared, so this is a cheap check that
+    /// the tree has not been corrupted through [`TreeNode::value_mut`] or
+    /// [`TreeNode::children_mut`], not a geometric validation.
+    pub fn bounds_consistent(&self) -> bool {
+        self.root
+            .as_ref()
+            .is_none_or(|root| root.children.iter().all(TreeNode::bounds_consistent))
+    }
 
-    #[test]
-    fn it_works() {
-        let path = Path::new("/home/cameron/Downloads/CAM.svg");
-        let lines = import_svg(path, 0.0001).unwrap();
+    /// Hash of the nesting and of every node's cached area and bounding rect.
+    /// Siblings are hashed in an order derived from their own hashes, so
+    /// trees that differ only in build order hash the same. The hash is
+    /// FNV-1a, which is stable across builds and platforms, so it can be
+    /// stored to detect changes later.
+    pub fn structural_hash(&self) -> u64 {
+        self.root
+            .as_ref()
+            .map_or(FNV_OFFSET, |root| root.children_hash(0, FNV_OFFSET))
+    }
 
-        let polygons: Vec<Polygon> = lines
+    /// Mean of every node's center point weighted by its area, from the
+    /// cached values. `None` when the tree is empty or has no area.
+    pub fn weighted_centroid(&self) -> Option<[f32; 2]> {
+        let mut sums = [0.0; 3];
+        self.root.as_ref()?.weighted_sums(&mut sums);
+
+        let [x, y, area] = sums;
+        (area > 0.0).then(|| [(x / area) as f32, (y / area) as f32])
+    }
+
+    /// Sum of the cached areas of every node, excluding the root. Nested
+    /// shapes are counted again on top of their containers.
+    pub fn total_area(&self) -> f32 {
+        self.signed_area_sum(|_| 1.0)
+    }
+
+    /// Area covered under the even-odd rule, from the cached areas: nodes at
+    /// even depth are solid and add their area, nodes at odd depth are holes
+    /// and subtract theirs. This assumes children lie entirely inside their
+    /// parent and siblings do not overlap, as with the rings of nested
+    /// polygons; it is not a union of arbitrary overlapping shapes.
+    pub fn covered_area(&self) -> f32 {
+        self.signed_area_sum(|depth| if depth % 2 == 0 { 1.0 } else { -1.0 })
+    }
+
+    fn signed_area_sum(&self, sign: impl Fn(usize) -> f32) -> f32 {
+        let mut sum = 0.0;
+        let mut stack: Vec<(usize, &TreeNode<T>)> = self
+            .root
+            .iter()
+            .flat_map(|root| &root.children)
+            .map(|node| (0, node))
+            .collect();
+        while let Some((depth, node)) = stack.pop() {
+            sum += sign(depth) * node.area;
+            stack.extend(node.children.iter().map(|child| (depth + 1, child)));
+        }
+        sum
+    }
+
+    /// Sum of [`Shape::perimeter`] over every node, excluding the root.
+    pub fn total_perimeter(&self) -> f32 {
+        self.iter().map(|(_, value)| value.perimeter()).sum()
+    }
+
+    /// Number of descendants of every node, in the same order as
+    /// [`Tree::iter`]. Computed in a single post-order pass.
+    pub fn descendant_counts(&self) -> Vec<(&T, usize)> {
+        let mut order: VecDeque<FoldedNode<T, usize>> = self
+            .fold_bottom_up(|_| 0, |acc, child| acc + child + 1)
+            .into();
+
+        let mut counts = Vec::new();
+        while let Some(node) = order.pop_front() {
+            counts.push((node.value, node.acc));
+            order.extend(node.children);
+        }
+        counts
+    }
+
+    /// Other children of the parent of the deepest node containing `point`.
+    /// Empty if no node contains the point.
+    pub fn siblings_at_point(&self, point: [f32; 2]) -> Vec<&T> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+        let path = root.path_at_point(&point);
+        let Some(hit) = path.last() else {
+            return Vec::new();
+        };
+        let parent = if path.len() > 1 {
+            path[path.len() - 2]
+        } else {
+            root
+        };
+
+        parent
+            .children
+            .iter()
+            .filter(|child| !core::ptr::eq(*child, *hit))
+            .map(|child| &child.value)
+            .collect()
+    }
+
+    /// Moves every node out of the tree, yielding `(depth, value)` like
+    /// [`Tree::into_iter`]. The root is kept, so the tree can be reused.
+    pub fn drain(&mut self) -> TreeNodeDepthIntoIterator<T> {
+        let Some(root) = &mut self.root else {
+            return TreeNodeDepthIntoIterator {
+                order: Default::default(),
+            };
+        };
+        root.subtree_bounds = AABB::new_empty();
+
+        let mut queue = VecDeque::new();
+        for child in core::mem::take(&mut root.children) {
+            queue.push_back((0, child));
+        }
+        TreeNodeDepthIntoIterator { order: queue }
+    }
+
+    /// Rebuilds the tree from its current values, largest first, as if they
+    /// had all been passed to `Tree::from` at once. Fixes shapes that were
+    /// inserted before the shapes that contain them.
+    pub fn rebuild(&mut self) {
+        let mut nodes: Vec<TreeNode<T>> = self.drain().map(|(_, value)| value.into()).collect();
+        nodes.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap());
+
+        for node in nodes {
+            self.insert(node);
+        }
+    }
+
+    /// Bulk-loads every node's children R-tree again, which packs and
+    /// rebalances it after incremental inserts. The hierarchy is unchanged.
+    pub fn shrink_to_fit(&mut self) {
+        if let Some(root) = &mut self.root {
+            root.shrink_to_fit();
+        }
+    }
+
+    /// Maps every value through the affine `matrix` (see
+    /// [`Transform::transform`]), recomputing each node's cached bounds,
+    /// center point and area and reloading the R-trees. The nesting is kept
+    /// as is.
+    pub fn transform(&mut self, matrix: [f32; 6])
+    where
+        T: Transform,
+    {
+        if let Some(root) = &mut self.root {
+            root.value = root.value.transform(matrix);
+            root.transform_children(matrix);
+            root.subtree_bounds = AABB::new_empty();
+            for child in &root.children {
+                root.subtree_bounds.merge(&child.subtree_bounds);
+            }
+        }
+    }
+
+    /// Removes every node that has exactly one child when
+    /// `is_redundant(parent, child)` holds, moving the child up into its
+    /// place. Chains of such nodes collapse to their innermost node.
+    pub fn collapse_redundant(&mut self, is_redundant: impl Fn(&T, &T) -> bool) {
+        if let Some(root) = &mut self.root {
+            root.collapse_redundant(&is_redundant);
+        }
+    }
+
+    /// Removes every node matching `pred` together with all its
+    /// descendants, returning the values of the removed nodes. Unlike
+    /// [`Tree::filter_map`], the children of a removed node go with it, and
+    /// matches inside a subtree that was already removed are not returned.
+    pub fn remove_subtree_where<F: Fn(&T) -> bool>(&mut self, pred: F) -> Vec<T> {
+        let mut removed = Vec::new();
+        if let Some(root) = &mut self.root {
+            root.remove_subtree_where(&pred, &mut removed);
+            root.subtree_bounds = AABB::new_empty();
+            for child in &root.children {
+                root.subtree_bounds.merge(&child.subtree_bounds);
+            }
+        }
+        removed
+    }
+
+    /// Inserts `value` under the deepest node that contains it, or at the top
+    /// level if none does, even if the root's own value does not contain it.
+    /// [Empty](Shape::is_empty) values are skipped.
+    pub fn insert<E>(&mut self, value: E)
+    where
+        E: Into<TreeNode<T>>,
+    {
+        if let Some(root) = &mut self.root {
+            root.add_node(value);
+        }
+    }
+
+    /// Like [`Tree::insert`], but reports where `value` ended up.
+    pub fn try_insert(&mut self, value: T) -> InsertOutcome {
+        if value.is_empty() {
+            return InsertOutcome::Skipped;
+        }
+        let outcome = match self.would_parent(&value) {
+            Some(parent) => InsertOutcome::Nested {
+                parent_area: parent.area(),
+            },
+            None => InsertOutcome::TopLevel,
+        };
+        self.insert(value);
+        outcome
+    }
+
+    /// Like [`Tree::insert`], but skips `value` if the tree already holds an
+    /// identical shape: one whose area and bounding rect match within
+    /// `epsilon` and which contains and is contained by `value`, or if
+    /// `value` is [empty](Shape::is_empty). Returns whether `value` was
+    /// inserted.
+    pub fn insert_dedup(&mut self, value: T, epsilon: f32) -> bool {
+        let Some(root) = &mut self.root else {
+            return false;
+        };
+        if value.is_empty() {
+            return false;
+        }
+
+        let elem = TreeNode::from(value);
+        if root.find_duplicate(&elem, epsilon, true) {
+            return false;
+        }
+        root.add_node(elem);
+        true
+    }
+
+    /// Removes every node identical, in the sense of [`Tree::insert_dedup`],
+    /// to a shallower node anywhere in the tree or to an earlier one at the
+    /// same depth. The children of a removed node move up to its parent.
+    pub fn dedup_by_geometry(&mut self, epsilon: f32) {
+        let Some(root) = &mut self.root else {
+            return;
+        };
+
+        // Flattened breadth first with parent indices, so every node comes
+        // after all shallower nodes.
+        let mut nodes: Vec<(Option<usize>, TreeNode<T>)> = Vec::new();
+        let mut queue: VecDeque<(Option<usize>, TreeNode<T>)> = core::mem::take(&mut root.children)
             .into_iter()
-            .map(|line| Polygon::new(line, Vec::new()))
+            .map(|child| (None, child))
             .collect();
+        while let Some((parent, mut node)) = queue.pop_front() {
+            let index = Some(nodes.len());
+            queue.extend(
+                core::mem::take(&mut node.children)
+                    .into_iter()
+                    .map(|child| (index, child)),
+            );
+            nodes.push((parent, node));
+        }
 
-        let tree: Tree<Polygon> = Tree::from_polygon(polygons);
-        let mut _count = 0;
-        for (_depth, _) in tree.iter() {
-            // println!("Depth: {}", depth);
-            _count += 1;
+        // Duplicates have areas within `epsilon`, so only neighbors in area
+        // order need the full comparison.
+        let mut by_area: Vec<usize> = (0..nodes.len()).collect();
+        by_area.sort_by(|&l, &r| {
+            nodes[l]
+                .1
+                .area
+                .partial_cmp(&nodes[r].1.area)
+                .unwrap_or(Ordering::Equal)
+        });
+        let mut rank = vec![0; nodes.len()];
+        for (r, &i) in by_area.iter().enumerate() {
+            rank[i] = r;
         }
 
-        // println!("COUNT: {}", count);
+        let mut removed = vec![false; nodes.len()];
+        for i in 0..nodes.len() {
+            let area = nodes[i].1.area;
+            let duplicate = by_area[..rank[i]]
+                .iter()
+                .rev()
+                .take_while(|&&j| area - nodes[j].1.area <= epsilon)
+                .chain(
+                    by_area[rank[i] + 1..]
+                        .iter()
+                        .take_while(|&&j| nodes[j].1.area - area <= epsilon),
+                )
+                .any(|&j| j < i && !removed[j] && nodes[j].1.is_duplicate(&nodes[i].1, epsilon));
+            removed[i] = duplicate;
+        }
+
+        // Children come after their parents, so building from the back
+        // finishes each node's children before the node itself.
+        let mut children: Vec<Vec<TreeNode<T>>> = (0..nodes.len()).map(|_| Vec::new()).collect();
+        let mut top_level = Vec::new();
+        while let Some((mut parent, mut node)) = nodes.pop() {
+            let index = nodes.len();
+            while let Some(p) = parent.filter(|&p| removed[p]) {
+                parent = nodes[p].0;
+            }
+            if removed[index] {
+                continue;
+            }
+
+            node.children = RTree::bulk_load(core::mem::take(&mut children[index]));
+            node.subtree_bounds = node.bounding_rect;
+            for child in &node.children {
+                node.subtree_bounds.merge(&child.subtree_bounds);
+            }
+            match parent {
+                Some(p) => children[p].push(node),
+                None => top_level.push(node),
+            }
+        }
+
+        root.children = RTree::bulk_load(top_level);
+        root.subtree_bounds = AABB::new_empty();
+        for child in &root.children {
+            root.subtree_bounds.merge(&child.subtree_bounds);
+        }
+    }
+
+    /// Like [`Tree::insert`], but picks among overlapping containers with
+    /// `policy` instead of taking the first one found.
+    pub fn insert_with_policy<E>(&mut self, value: E, policy: &ContainmentPolicy<T>)
+    where
+        E: Into<TreeNode<T>>,
+    {
+        if let Some(root) = &mut self.root {
+            root.add_node_with_policy(value, policy);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root
+            .as_ref()
+            .is_none_or(|root| root.children.size() == 0)
+    }
+
+    /// Number of nodes, excluding the root.
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, TreeNode::descendant_count)
+    }
+
+    /// Number of nodes whose value satisfies `pred`, counted in one
+    /// traversal without collecting.
+    pub fn count_where<F: Fn(&T) -> bool>(&self, pred: F) -> usize {
+        self.root.as_ref().map_or(0, |root| root.count_where(&pred))
+    }
+
+    /// Number of nodes without children, excluding the root.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_and_internal_counts().0
+    }
+
+    /// Number of nodes with at least one child, excluding the root.
+    pub fn internal_count(&self) -> usize {
+        self.leaf_and_internal_counts().1
+    }
+
+    fn leaf_and_internal_counts(&self) -> (usize, usize) {
+        let mut counts = (0, 0);
+        let mut stack: Vec<&TreeNode<T>> =
+            self.root.iter().flat_map(|root| &root.children).collect();
+        while let Some(node) = stack.pop() {
+            if node.children.size() == 0 {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+                stack.extend(&node.children);
+            }
+        }
+        counts
+    }
+
+    /// Innermost value containing `point`, if any.
+    pub fn deepest_at_point(&self, point: [f32; 2]) -> Option<&T> {
+        let root = self.root.as_ref()?;
+        root.path_at_point(&point).pop().map(|node| &node.value)
+    }
+
+    /// What a click at `point` selects: the innermost value containing it, or
+    /// failing that the value nearest to it by [`Shape::distance_to_point`].
+    /// Nested values are considered too, and ties go to the smaller area, so
+    /// a shape touching its container's edge wins over the container.
+    /// Subtrees whose bounds are farther than the best distance so far are
+    /// skipped. `None` only for an empty tree.
+    pub fn pick(&self, point: [f32; 2]) -> Option<Pick<&T>> {
+        if let Some(value) = self.deepest_at_point(point) {
+            return Some(Pick::Inside(value));
+        }
+
+        let mut nearest: Option<(f32, &TreeNode<T>)> = None;
+        let mut stack: Vec<&TreeNode<T>> = self.root.as_ref()?.children.iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Some((best, _)) = nearest
+                && crate::float::sqrt(node.subtree_bounds.distance_2(&point)) > best
+            {
+                continue;
+            }
+
+            let distance = node.value.distance_to_point(point);
+            let closer = nearest.is_none_or(|(best, best_node)| {
+                distance < best || (distance == best && node.area < best_node.area)
+            });
+            if closer {
+                nearest = Some((distance, node));
+            }
+            stack.extend(node.children.iter());
+        }
+
+        nearest.map(|(distance, node)| Pick::Nearest(&node.value, distance))
+    }
+
+    /// The deepest node containing `point`, for reading the region below
+    /// it through [`TreeNode::iter`] or [`TreeNode::children`] without
+    /// copying anything out of the tree.
+    pub fn subtree_at_point(&self, point: [f32; 2]) -> Option<&TreeNode<T>> {
+        self.root.as_ref()?.path_at_point(&point).pop()
+    }
+
+    /// Every value containing `point`, innermost first.
+    pub fn stack_at_point(&self, point: [f32; 2]) -> Vec<&T> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        root.path_at_point(&point)
+            .into_iter()
+            .rev()
+            .map(|node| &node.value)
+            .collect()
+    }
+
+    /// Deepest value containing both `a` and `b`, the spatial counterpart of
+    /// a lowest common ancestor. `None` if no single shape contains both.
+    pub fn common_container(&self, a: [f32; 2], b: [f32; 2]) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        let mut found = None;
+        while let Some(child) = node
+            .children
+            .locate_all_at_point(&a)
+            .find(|child| child.value.contains_point(a) && child.value.contains_point(b))
+        {
+            found = Some(&child.value);
+            node = child;
+        }
+        found
+    }
+
+    /// Number of nodes containing `point`, i.e. the depth of the deepest one
+    /// plus one. 0 if the point is outside every shape.
+    pub fn depth_at_point(&self, point: [f32; 2]) -> usize {
+        let Some(mut node) = self.root.as_ref() else {
+            return 0;
+        };
+
+        let mut depth = 0;
+        while let Some(child) = node
+            .children
+            .locate_all_at_point(&point)
+            .find(|child| child.value.contains_point(point))
+        {
+            depth += 1;
+            node = child;
+        }
+        depth
+    }
+
+    /// Number of direct children of the deepest node containing `point`, or
+    /// `None` if no node contains it.
+    pub fn direct_child_count_at_point(&self, point: [f32; 2]) -> Option<usize> {
+        let root = self.root.as_ref()?;
+        let hit = root.path_at_point(&point).pop()?;
+        Some(hit.children.size())
+    }
+
+    /// Whether `point` is filled under the even-odd rule. Assumes nesting
+    /// alternates between solid regions and holes: a top-level shape is solid,
+    /// a shape inside it is a hole, a shape inside that is solid again.
+    pub fn is_inside_even_odd(&self, point: [f32; 2]) -> bool {
+        self.depth_at_point(point) % 2 == 1
+    }
+
+    /// Values at any depth whose bounding rect contains `point` but whose
+    /// shape does not, such as a concave polygon with `point` in a notch or
+    /// a polygon with `point` in a hole. These are the shapes that a
+    /// bounding-rect pre-filter lets through for nothing. Parents come
+    /// before their descendants.
+    pub fn bbox_only_hits(&self, point: [f32; 2]) -> Vec<&T> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            root.bbox_only_hits(&point, &mut found);
+        }
+        found
+    }
+
+    /// Values at any depth within `max_distance_2` (squared) of `point`. The
+    /// distance is the one used by the R-tree: 0 if the shape contains the
+    /// point, otherwise the squared distance to its center point.
+    pub fn within_distance(&self, point: [f32; 2], max_distance_2: f32) -> Vec<&T> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            root.within_distance(&point, max_distance_2, &mut found);
+        }
+        found
+    }
+
+    /// Up to `k` values at any depth, closest first under the same distance
+    /// as [`Tree::within_distance`]. Values at equal distance keep their
+    /// breadth-first order.
+    pub fn k_nearest(&self, point: [f32; 2], k: usize) -> Vec<&T> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        let mut nodes = Vec::new();
+        let mut queue: VecDeque<&TreeNode<T>> = root.children.iter().collect();
+        while let Some(node) = queue.pop_front() {
+            nodes.push((node.distance_2(&point), &node.value));
+            queue.extend(node.children.iter());
+        }
+        nodes.sort_by(|l, r| l.0.total_cmp(&r.0));
+
+        nodes.into_iter().take(k).map(|(_, value)| value).collect()
+    }
+
+    /// Entry for the deepest node containing `point`: `Occupied` with its value
+    /// if one exists, otherwise `Vacant` so a new shape can be inserted.
+    ///
+    /// Changing the geometry of an occupied value does not re-index it.
+    pub fn entry_at_point(&mut self, point: [f32; 2]) -> Entry<'_, T> {
+        let occupied = self.root.as_ref().is_some_and(|root| {
+            root.children
+                .locate_all_at_point(&point)
+                .any(|child| child.value.contains_point(point))
+        });
+
+        if occupied {
+            let child = self
+                .root
+                .as_mut()
+                .unwrap()
+                .children
+                .locate_all_at_point_mut(&point)
+                .find(|child| child.value.contains_point(point))
+                .unwrap();
+            Entry::Occupied(&mut child.deepest_at_point_mut(&point).value)
+        } else {
+            Entry::Vacant(VacantEntry { tree: self, point })
+        }
+    }
+
+    /// Swaps the value of the deepest node containing `point` for
+    /// `new_value` and returns the old one, or `None` if no node contains
+    /// `point`. Meant for changing non-geometric data: the node keeps the
+    /// bounds, center point and area cached from the old value, so if
+    /// `new_value` has a different outline call [`Tree::rebuild`] afterwards.
+    pub fn replace_value_at_point(&mut self, point: [f32; 2], new_value: T) -> Option<T> {
+        match self.entry_at_point(point) {
+            Entry::Occupied(value) => Some(core::mem::replace(value, new_value)),
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Moves the deepest node containing `point`, with its subtree, through
+    /// the affine `matrix` (see [`Transform::transform`]) and inserts it again
+    /// under whatever now contains it. Returns `false` if no node contains
+    /// `point`. Like [`Tree::insert`], this does not move nodes the shape
+    /// now covers underneath it; call [`Tree::rebuild`] if that can happen.
+    pub fn relocate_at_point(&mut self, point: [f32; 2], matrix: [f32; 6]) -> bool
+    where
+        T: Transform,
+    {
+        let Some(root) = &mut self.root else {
+            return false;
+        };
+        let Some(node) = root.take_deepest_at_point(&point) else {
+            return false;
+        };
+
+        root.subtree_bounds = AABB::new_empty();
+        for child in &root.children {
+            root.subtree_bounds.merge(&child.subtree_bounds);
+        }
+        root.add_node(node.transformed(matrix));
+        true
+    }
+
+    /// Maps every value through `f`, dropping those mapped to `None`. The
+    /// children of a dropped node move up to its nearest kept ancestor, or
+    /// to the top level, so the kept values stay nested as before. `root`
+    /// becomes the new root's value. Cached geometry is recomputed from the
+    /// new values, but containment is not re-checked.
+    pub fn filter_map<U, F>(&self, root: U, f: F) -> Tree<U>
+    where
+        U: Shape + Clone,
+        F: Fn(&T) -> Option<U>,
+    {
+        let mut top_level = Vec::new();
+        if let Some(node) = &self.root {
+            node.filter_map_children(&f, &mut top_level);
+        }
+        Tree::from_top_level(root, top_level)
+    }
+
+    /// Copy of the tree with each value tagged by `f(value)`. The tag comes
+    /// first so the pairs use the tagged tuple [`Shape`] impl; the structure
+    /// and cached geometry are copied, not recomputed.
+    pub fn annotate<M, F>(&self, f: F) -> Tree<(M, T)>
+    where
+        M: Clone,
+        F: Fn(&T) -> M,
+    {
+        Tree {
+            root: self.root.as_ref().map(|root| root.annotate(&f)),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Read-only copy of the tree that later changes to `self` do not
+    /// affect. The tree is cloned once; cloning the snapshot is cheap.
+    pub fn snapshot(&self) -> TreeSnapshot<T> {
+        TreeSnapshot {
+            tree: Arc::new(self.clone()),
+        }
+    }
+
+    /// Copy of the structure whose values are wrapped in [`Arc`]. Each value
+    /// is cloned once into its `Arc`; cloning the returned tree afterwards
+    /// only bumps reference counts. Node metadata is carried over as is.
+    pub fn shallow_clone_structure(&self) -> Tree<Arc<T>> {
+        Tree {
+            root: self.root.as_ref().map(|root| root.shared()),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Like [`Tree::shallow_clone_structure`], but moves the values into
+    /// their `Arc`s instead of cloning them.
+    pub fn into_shared(self) -> Tree<Arc<T>> {
+        Tree {
+            root: self.root.map(|root| root.into_shared()),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Empty tree below `root` that reserves room for `capacity` top-level
+    /// nodes the first time it is [extended](Extend). rstar's R-trees cannot
+    /// reserve space themselves, so the reservation is held by the `Vec` the
+    /// top-level nodes are gathered in before they are bulk-loaded.
+    pub fn with_capacity(root: T, capacity: usize) -> Self {
+        let mut tree = Self::from_top_level(root, Vec::new());
+        tree.capacity = capacity;
+        tree
+    }
+
+    /// Tree of `values` below `root`, whose bounding rect, center point and
+    /// area are cached like any other node's. The values are sorted largest
+    /// first but not checked against `root`, so shapes outside it still
+    /// become top-level nodes.
+    pub fn with_root(root: T, values: Vec<T>) -> Self {
+        let mut nodes: Vec<TreeNode<T>> = values.into_iter().map(TreeNode::from).collect();
+        nodes.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap_or(Ordering::Equal));
+
+        let children = Self::from_nodes(root.clone(), nodes).into_top_level_nodes();
+        Self::from_root(TreeNode::with_children(root, children))
+    }
+
+    /// Tree with `node` as its root. The root is synthetic: its children are
+    /// the top-level nodes and its own value is never queried.
+    pub fn from_root(mut node: TreeNode<T>) -> Self {
+        node.subtree_bounds = AABB::new_empty();
+        for child in &node.children {
+            node.subtree_bounds.merge(&child.subtree_bounds);
+        }
+
+        Self {
+            root: Some(node),
+            capacity: 0,
+        }
+    }
+
+    /// The top-level nodes as owned values, each keeping its subtree. They
+    /// can be moved into another tree with [`Tree::insert`] or
+    /// [`TreeNode::with_children`].
+    pub fn into_top_level_nodes(self) -> Vec<TreeNode<T>> {
+        match self.root {
+            Some(root) => root.children.into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// One tree per top-level node, each holding that node's subtree under a
+    /// clone of this tree's root value.
+    pub fn split_by_top_level(self) -> Vec<Tree<T>> {
+        let Some(root) = self.root else {
+            return Vec::new();
+        };
+
+        root.children
+            .into_iter()
+            .map(|child| Tree::from_top_level(root.value.clone(), vec![child]))
+            .collect()
+    }
+
+    /// Whether the root's own value contains `point`, i.e. whether `point`
+    /// is inside the tree at depth -1. Always false for a placeholder root
+    /// without area.
+    pub fn root_contains_point(&self, point: [f32; 2]) -> bool {
+        self.root
+            .as_ref()
+            .is_some_and(|root| root.value.contains_point(point))
+    }
+
+    pub fn root(&self) -> &Option<TreeNode<T>> {
+        &self.root
+    }
+
+    /// Mutable access to the root. See [`TreeNode::children_mut`] for what
+    /// may safely be changed.
+    pub fn root_mut(&mut self) -> Option<&mut TreeNode<T>> {
+        self.root.as_mut()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> Tree<T>
+where
+    T: Shape + Clone + Sync,
+{
+    /// Parallel iterator over `(depth, value)` pairs. The nodes are collected
+    /// breadth-first up front, but the order in which they are processed is
+    /// unspecified.
+    pub fn par_iter(&self) -> impl rayon::iter::ParallelIterator<Item = (usize, &T)> {
+        use rayon::iter::IntoParallelIterator;
+
+        self.iter().collect::<Vec<_>>().into_par_iter()
+    }
+}
+
+/// Trees are equal when they nest equal values the same way below the root.
+/// Siblings are compared in order of area, then center point, so the R-tree's
+/// internal layout does not matter.
+impl<T> PartialEq for Tree<T>
+where
+    T: Shape + Clone + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(l), Some(r)) => l.children_eq(r),
+            (l, r) => {
+                let is_empty = |root: &Option<TreeNode<T>>| {
+                    root.as_ref().is_none_or(|root| root.children.size() == 0)
+                };
+                is_empty(l) && is_empty(r)
+            }
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Tree<T>
+where
+    T: Shape + Clone + Default,
+{
+    fn from(value: Vec<T>) -> Self {
+        Self::from((value, Default::default()))
+    }
+}
+
+impl<T> From<(Vec<T>, T)> for Tree<T>
+where
+    T: Shape + Clone,
+{
+    fn from(value: (Vec<T>, T)) -> Self {
+        Self::from_nodes(value.1, value.0.into_iter().map(TreeNode::from).collect())
+    }
+}
+
+impl<T> Tree<T>
+where
+    T: Shape + Clone,
+{
+    /// Tree whose synthetic root holds `root` and has `children` as its
+    /// top-level nodes, as given.
+    pub(crate) fn from_top_level(root: T, children: Vec<TreeNode<T>>) -> Self {
+        let mut subtree_bounds = AABB::new_empty();
+        for child in &children {
+            subtree_bounds.merge(&child.subtree_bounds);
+        }
+
+        Self {
+            root: Some(TreeNode {
+                value: root,
+                bounding_rect: AABB::from_corners([0.0, 0.0], [0.0, 0.0]),
+                center_point: [0.0, 0.0],
+                children: RTree::bulk_load(children),
+                area: 0.0,
+                subtree_bounds,
+            }),
+            capacity: 0,
+        }
+    }
+
+    /// Inserts `nodes` in order under a root holding `root`. The top-level
+    /// nodes are gathered in a `Vec` sized for every input node and
+    /// bulk-loaded once instead of growing the root's R-tree one insert at a
+    /// time.
+    fn from_nodes(root: T, nodes: Vec<TreeNode<T>>) -> Self {
+        let capacity = nodes.len();
+        Self::from_top_level(root, Self::top_level_of(nodes, capacity, 0.0))
+    }
+
+    /// Nests `nodes` in order, with containment tested within `tolerance`,
+    /// and returns those that ended up at the top level. They are collected
+    /// in a `Vec` with room for `capacity` of them, which is shrunk to fit
+    /// before it is returned. At most every node ends up at the top level,
+    /// so `nodes.len()` is enough to never reallocate while nesting.
+    fn top_level_of(nodes: Vec<TreeNode<T>>, capacity: usize, tolerance: f32) -> Vec<TreeNode<T>> {
+        let mut top_level: Vec<TreeNode<T>> = Vec::with_capacity(capacity);
+        for elem in nodes.into_iter().filter(|elem| !elem.value.is_empty()) {
+            if !top_level
+                .iter_mut()
+                .any(|child| child.add_node_tree_node(&elem, tolerance))
+            {
+                top_level.push(elem);
+            }
+        }
+        top_level.shrink_to_fit();
+        top_level
+    }
+}
+
+impl<T> Tree<T>
+where
+    T: Shape + Clone + Default,
+{
+    /// Builds a tree by inserting values as they arrive, without collecting
+    /// the input first. A shape that arrives after shapes it contains starts
+    /// out as their sibling, so one settling pass runs at the end to nest
+    /// siblings under each other where they fit. Unlike the sorted
+    /// [`FromIterator`] impl, a shape that ended up inside an unrelated
+    /// subtree is not moved back out.
+    pub fn from_iter_streaming<I: IntoIterator<Item = T>>(iter: I) -> Tree<T> {
+        let mut root: TreeNode<T> = TreeNode::from(T::default());
+        root.subtree_bounds = AABB::new_empty();
+
+        for shape in iter {
+            root.add_node(shape);
+        }
+        root.resettle();
+
+        Tree {
+            root: Some(root),
+            capacity: 0,
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Tree<T>
+where
+    T: Shape + Clone + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Tree<T> {
+        let mut arr: Vec<TreeNode<T>> = iter.into_iter().map(|elem| elem.into()).collect();
+        arr.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap());
+
+        Self::from_nodes(T::default(), arr)
+    }
+}
+
+/// Inserts the items largest first, so shapes in the batch nest correctly
+/// whatever their order. An empty tree gathers its top-level nodes first and
+/// bulk-loads them, using the room reserved by [`Tree::with_capacity`].
+/// Nodes already in the tree are not moved under a larger incoming shape;
+/// call [`Tree::rebuild`] if that can happen.
+impl<T> Extend<T> for Tree<T>
+where
+    T: Shape + Clone,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut nodes: Vec<TreeNode<T>> = iter.into_iter().map(TreeNode::from).collect();
+        nodes.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap_or(Ordering::Equal));
+
+        if let Some(root) = &mut self.root
+            && root.children.size() == 0
+        {
+            let top_level = Self::top_level_of(nodes, self.capacity, 0.0);
+            root.subtree_bounds = AABB::new_empty();
+            for child in &top_level {
+                root.subtree_bounds.merge(&child.subtree_bounds);
+            }
+            root.children = RTree::bulk_load(top_level);
+            return;
+        }
+
+        for node in nodes {
+            self.insert(node);
+        }
+    }
+}
+
+// MARK: TreeNode
+
+impl<T> RTreeObject for TreeNode<T>
+where
+    T: Shape + Clone,
+{
+    type Envelope = AABB<AABBType>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.bounding_rect
+    }
+}
+
+impl<T> PointDistance for TreeNode<T>
+where
+    T: Shape + Clone,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as rstar::Envelope>::Point,
+    ) -> <<Self::Envelope as rstar::Envelope>::Point as rstar::Point>::Scalar {
+        if self.value.contains_point(*point) {
+            0.0
+        } else {
+            self.center_point.distance_2(point)
+        }
+    }
+}
+
+impl<T> From<T> for TreeNode<T>
+where
+    T: Shape + Clone,
+{
+    fn from(value: T) -> Self {
+        let r = value.bounding_rect();
+        let center_point = value.center_point();
+        let area = value.area();
+        let bounding_rect = AABB::from_corners(r.0, r.1);
+        Self {
+            value,
+            bounding_rect,
+            center_point,
+            children: RTree::new(),
+            area,
+            subtree_bounds: bounding_rect,
+        }
+    }
+}
+
+impl<T> TreeNode<T>
+where
+    T: Shape + Clone,
+{
+    /// Node for `value` with `children` below it as given, without checking
+    /// containment. The metadata is computed from `value` and the children
+    /// are bulk-loaded into the R-tree.
+    pub fn with_children(value: T, children: Vec<TreeNode<T>>) -> Self {
+        let mut node = Self::from(value);
+        for child in &children {
+            node.subtree_bounds.merge(&child.subtree_bounds);
+        }
+        node.children = RTree::bulk_load(children);
+        node
+    }
+
+    /// Inserts `elem` under the deepest descendant that contains it, or as a
+    /// direct child. This node's own value is not tested, which is what lets
+    /// the root accept every shape.
+    pub fn add_node<E>(&mut self, elem: E)
+    where
+        E: Into<TreeNode<T>>,
+    {
+        self.add_node_within(elem.into(), 0.0);
+    }
+
+    /// Like [`TreeNode::add_node`], nesting `elem` in shapes that hold it
+    /// within `tolerance` (see [`contains_within`]).
+    fn add_node_within(&mut self, elem: TreeNode<T>, tolerance: f32) {
+        if elem.value.is_empty() {
+            return;
+        }
+        self.subtree_bounds.merge(&elem.subtree_bounds);
+
+        for child in &mut self.children {
+            if child.add_node_tree_node(&elem, tolerance) {
+                return;
+            }
+        }
+
+        self.children.insert(elem);
+    }
+
+    fn add_node_tree_node(&mut self, elem: &TreeNode<T>, tolerance: f32) -> bool {
+        if !contains_within(&self.value, &elem.value, tolerance) {
+            return false;
+        }
+
+        self.subtree_bounds.merge(&elem.subtree_bounds);
+        // A shape nested only thanks to the tolerance may have its center
+        // outside the child that holds it, so search by envelope instead.
+        let nested = if tolerance > 0.0 {
+            self.children
+                .locate_in_envelope_intersecting_mut(&elem.bounding_rect)
+                .any(|child| child.add_node_tree_node(elem, tolerance))
+        } else {
+            self.children
+                .locate_all_at_point_mut(&elem.center_point)
+                .any(|child| child.add_node_tree_node(elem, tolerance))
+        };
+        if !nested {
+            self.children.insert(elem.clone());
+        }
+        true
+    }
+
+    pub fn add_node_with_policy<E>(&mut self, elem: E, policy: &ContainmentPolicy<T>)
+    where
+        E: Into<TreeNode<T>>,
+    {
+        self.add_node_with_policy_within(elem.into(), policy, 0.0);
+    }
+
+    /// Like [`TreeNode::add_node_with_policy`], with containment tested
+    /// within `tolerance`.
+    fn add_node_with_policy_within(
+        &mut self,
+        elem: TreeNode<T>,
+        policy: &ContainmentPolicy<T>,
+        tolerance: f32,
+    ) {
+        if elem.value.is_empty() {
+            return;
+        }
+        self.subtree_bounds.merge(&elem.subtree_bounds);
+
+        let candidates = self
+            .children
+            .iter_mut()
+            .filter(|child| contains_within(&child.value, &elem.value, tolerance))
+            .collect();
+        match policy.choose(candidates) {
+            Some(child) => child.add_contained_with_policy(elem, policy, tolerance),
+            None => self.children.insert(elem),
+        }
+    }
+
+    /// Inserts `elem`, already known to be contained by this node, under the
+    /// child chosen by `policy`.
+    fn add_contained_with_policy(
+        &mut self,
+        elem: TreeNode<T>,
+        policy: &ContainmentPolicy<T>,
+        tolerance: f32,
+    ) {
+        self.subtree_bounds.merge(&elem.subtree_bounds);
+
+        let candidates: Vec<&mut TreeNode<T>> = if tolerance > 0.0 {
+            self.children
+                .locate_in_envelope_intersecting_mut(&elem.bounding_rect)
+                .filter(|child| contains_within(&child.value, &elem.value, tolerance))
+                .collect()
+        } else {
+            self.children
+                .locate_all_at_point_mut(&elem.center_point)
+                .filter(|child| child.value.contains_shape(&elem.value))
+                .collect()
+        };
+        match policy.choose(candidates) {
+            Some(child) => child.add_contained_with_policy(elem, policy, tolerance),
+            None => self.children.insert(elem),
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        let mut children: Vec<TreeNode<T>> =
+            core::mem::take(&mut self.children).into_iter().collect();
+        for child in &mut children {
+            child.shrink_to_fit();
+        }
+        self.children = RTree::bulk_load(children);
+    }
+
+    fn transform_children(&mut self, matrix: [f32; 6])
+    where
+        T: Transform,
+    {
+        let children: Vec<TreeNode<T>> = core::mem::take(&mut self.children)
+            .into_iter()
+            .map(|child| child.transformed(matrix))
+            .collect();
+        self.children = RTree::bulk_load(children);
+    }
+
+    /// This node with its value and every descendant mapped through `matrix`
+    /// and their cached geometry recomputed.
+    fn transformed(self, matrix: [f32; 6]) -> Self
+    where
+        T: Transform,
+    {
+        let mut node = TreeNode::from(self.value.transform(matrix));
+        node.children = self.children;
+        node.transform_children(matrix);
+        for child in &node.children {
+            node.subtree_bounds.merge(&child.subtree_bounds);
+        }
+        node
+    }
+
+    /// Removes the deepest descendant containing `point` together with its
+    /// subtree, shrinking the subtree bounds along the path to it.
+    fn take_deepest_at_point(&mut self, point: &[f32; 2]) -> Option<TreeNode<T>> {
+        let contains = |node: &TreeNode<T>| node.value.contains_point(*point);
+        let hit = self
+            .children
+            .locate_all_at_point(point)
+            .find(|child| contains(child))?;
+        // Overlapping siblings may all contain `point`, so the node that was
+        // checked is found again by address rather than by another search.
+        let selected = SelectNode {
+            node: hit,
+            envelope: hit.bounding_rect,
+        };
+
+        if hit.children.locate_all_at_point(point).any(contains) {
+            let hit = self
+                .children
+                .locate_all_at_point_mut(point)
+                .find(|child| core::ptr::eq(&**child, selected.node))?;
+            let taken = hit.take_deepest_at_point(point);
+            hit.subtree_bounds = hit.bounding_rect;
+            for child in &hit.children {
+                hit.subtree_bounds.merge(&child.subtree_bounds);
+            }
+            return taken;
+        }
+
+        self.children.remove_with_selection_function(selected)
+    }
+
+    fn collapse_redundant<F: Fn(&T, &T) -> bool>(&mut self, is_redundant: &F) {
+        let children: Vec<TreeNode<T>> = core::mem::take(&mut self.children)
+            .into_iter()
+            .map(|mut child| {
+                child.collapse_redundant(is_redundant);
+                while child.children.size() == 1
+                    && child
+                        .children
+                        .iter()
+                        .all(|only| is_redundant(&child.value, &only.value))
+                {
+                    child = child.children.into_iter().next().unwrap();
+                }
+                child
+            })
+            .collect();
+        self.children = RTree::bulk_load(children);
+    }
+
+    fn remove_subtree_where<F: Fn(&T) -> bool>(&mut self, pred: &F, removed: &mut Vec<T>) {
+        let mut kept = Vec::new();
+        for mut child in core::mem::take(&mut self.children) {
+            if pred(&child.value) {
+                removed.push(child.value);
+                continue;
+            }
+            child.remove_subtree_where(pred, removed);
+            child.subtree_bounds = child.bounding_rect;
+            for grandchild in &child.children {
+                child.subtree_bounds.merge(&grandchild.subtree_bounds);
+            }
+            kept.push(child);
+        }
+        self.children = RTree::bulk_load(kept);
+    }
+
+    /// Re-inserts the children of every node largest first, so a child that
+    /// contains its siblings becomes their parent.
+    fn resettle(&mut self) {
+        let mut children: Vec<TreeNode<T>> =
+            core::mem::take(&mut self.children).into_iter().collect();
+        children.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap());
+
+        for elem in children {
+            if !self
+                .children
+                .iter_mut()
+                .any(|child| child.add_node_tree_node(&elem, 0.0))
+            {
+                self.children.insert(elem);
+            }
+        }
+
+        for child in &mut self.children {
+            child.resettle();
+        }
+    }
+
+    fn bbox_only_hits<'a>(&'a self, point: &[f32; 2], found: &mut Vec<&'a T>) {
+        // `locate_all_at_point` goes through `PointDistance`, which already
+        // tests the shape, so query the envelopes directly.
+        let query = AABB::from_point(*point);
+        for child in self.children.locate_in_envelope_intersecting(&query) {
+            if !child.value.contains_point(*point) {
+                found.push(&child.value);
+            }
+            child.bbox_only_hits(point, found);
+        }
+    }
+
+    fn within_distance<'a>(
+        &'a self,
+        point: &[f32; 2],
+        max_distance_2: f32,
+        found: &mut Vec<&'a T>,
+    ) {
+        let radius = crate::float::sqrt(max_distance_2);
+        let query = AABB::from_corners(
+            [point[0] - radius, point[1] - radius],
+            [point[0] + radius, point[1] + radius],
+        );
+
+        // Descendants lie inside their parent's bounding rect, so a child whose
+        // center is too far away may still have descendants in range.
+        for child in self.children.locate_in_envelope_intersecting(&query) {
+            if child.bounding_rect.distance_2(point) > max_distance_2 {
+                continue;
+            }
+            if child.distance_2(point) <= max_distance_2 {
+                found.push(&child.value);
+            }
+            child.within_distance(point, max_distance_2, found);
+        }
+    }
+
+    fn enclosing<'a>(
+        &'a self,
+        shape: &T,
+        envelope: &AABB<AABBType>,
+        found: &mut Vec<&'a TreeNode<T>>,
+    ) {
+        for child in self.children.locate_in_envelope_intersecting(envelope) {
+            if child.bounding_rect.contains_envelope(envelope) && child.value.contains_shape(shape)
+            {
+                found.push(child);
+                child.enclosing(shape, envelope, found);
+            }
+        }
+    }
+
+    /// Follows the same descent as insertion and reports whether it passes
+    /// through a node identical to `elem`. `top_level` mirrors `add_node`
+    /// scanning every child of the root rather than querying the R-tree.
+    fn find_duplicate(&self, elem: &TreeNode<T>, epsilon: f32, top_level: bool) -> bool {
+        let mut candidates: Box<dyn Iterator<Item = &TreeNode<T>>> = if top_level {
+            Box::new(self.children.iter())
+        } else {
+            Box::new(self.children.locate_all_at_point(&elem.center_point))
+        };
+
+        candidates
+            .find(|child| child.value.contains_shape(&elem.value))
+            .is_some_and(|child| {
+                child.is_duplicate(elem, epsilon) || child.find_duplicate(elem, epsilon, false)
+            })
+    }
+
+    fn is_duplicate(&self, other: &TreeNode<T>, epsilon: f32) -> bool {
+        let close = |l: [f32; 2], r: [f32; 2]| {
+            (l[0] - r[0]).abs() <= epsilon && (l[1] - r[1]).abs() <= epsilon
+        };
+
+        (self.area - other.area).abs() <= epsilon
+            && close(self.bounding_rect.lower(), other.bounding_rect.lower())
+            && close(self.bounding_rect.upper(), other.bounding_rect.upper())
+            && self.value.contains_shape(&other.value)
+            && other.value.contains_shape(&self.value)
+    }
+
+    fn would_parent(&self, shape: &T, center_point: &[f32; 2]) -> Option<&T> {
+        if !self.value.contains_shape(shape) {
+            return None;
+        }
+
+        self.children
+            .locate_all_at_point(center_point)
+            .find_map(|child| child.would_parent(shape, center_point))
+            .or(Some(&self.value))
+    }
+
+    fn find_overlapping_siblings<'a>(&'a self, overlapping: &mut Vec<(&'a T, &'a T)>) {
+        let children = self.children();
+        for (i, l) in children.iter().enumerate() {
+            for r in &children[i + 1..] {
+                if l.value.intersects(&r.value) {
+                    overlapping.push((&l.value, &r.value));
+                }
+            }
+            l.find_overlapping_siblings(overlapping);
+        }
+    }
+
+    /// Chain of descendants containing `point`, from a direct child of this
+    /// node down to the deepest one.
+    fn path_at_point(&self, point: &[f32; 2]) -> Vec<&TreeNode<T>> {
+        let mut path = Vec::new();
+        let mut node = self;
+        while let Some(child) = node
+            .children
+            .locate_all_at_point(point)
+            .find(|child| child.value.contains_point(*point))
+        {
+            path.push(child);
+            node = child;
+        }
+        path
+    }
+
+    fn deepest_at_point_mut(&mut self, point: &[f32; 2]) -> &mut TreeNode<T> {
+        let has_child = self
+            .children
+            .locate_all_at_point(point)
+            .any(|child| child.value.contains_point(*point));
+
+        if has_child {
+            self.children
+                .locate_all_at_point_mut(point)
+                .find(|child| child.value.contains_point(*point))
+                .unwrap()
+                .deepest_at_point_mut(point)
+        } else {
+            self
+        }
+    }
+
+    /// Pushes the mapped children of this node onto `out`, replacing each
+    /// dropped child by its own mapped children.
+    fn filter_map_children<U, F>(&self, f: &F, out: &mut Vec<TreeNode<U>>)
+    where
+        U: Shape + Clone,
+        F: Fn(&T) -> Option<U>,
+    {
+        for child in &self.children {
+            match f(&child.value) {
+                Some(value) => {
+                    let mut children = Vec::new();
+                    child.filter_map_children(f, &mut children);
+                    out.push(TreeNode::with_children(value, children));
+                }
+                None => child.filter_map_children(f, out),
+            }
+        }
+    }
+
+    fn annotate<M: Clone, F: Fn(&T) -> M>(&self, f: &F) -> TreeNode<(M, T)> {
+        TreeNode {
+            value: (f(&self.value), self.value.clone()),
+            bounding_rect: self.bounding_rect,
+            center_point: self.center_point,
+            children: RTree::bulk_load(
+                self.children
+                    .iter()
+                    .map(|child| child.annotate(f))
+                    .collect(),
+            ),
+            area: self.area,
+            subtree_bounds: self.subtree_bounds,
+        }
+    }
+
+    fn shared(&self) -> TreeNode<Arc<T>> {
+        TreeNode {
+            value: Arc::new(self.value.clone()),
+            bounding_rect: self.bounding_rect,
+            center_point: self.center_point,
+            children: RTree::bulk_load(self.children.iter().map(|child| child.shared()).collect()),
+            area: self.area,
+            subtree_bounds: self.subtree_bounds,
+        }
+    }
+
+    fn into_shared(self) -> TreeNode<Arc<T>> {
+        TreeNode {
+            value: Arc::new(self.value),
+            bounding_rect: self.bounding_rect,
+            center_point: self.center_point,
+            children: RTree::bulk_load(
+                self.children
+                    .into_iter()
+                    .map(|child| child.into_shared())
+                    .collect(),
+            ),
+            area: self.area,
+            subtree_bounds: self.subtree_bounds,
+        }
+    }
+
+    /// Breadth-first iterator over this node's descendants, like
+    /// [`Tree::iter`] with this node as the root: its direct children are at
+    /// depth 0 and its own value is not yielded.
+    pub fn iter(&self) -> TreeNodeDepthIterator<'_, T> {
+        let mut queue = VecDeque::new();
+        for child in &self.children {
+            queue.push_back((0, child));
+        }
+        TreeNodeDepthIterator { order: queue }
+    }
+
+    fn visit<V: TreeVisitor<T>>(&self, depth: usize, visitor: &mut V) {
+        visitor.enter(depth, &self.value);
+        for child in &self.children {
+            child.visit(depth + 1, visitor);
+        }
+        visitor.leave(depth, &self.value);
+    }
+
+    fn bounds_consistent(&self) -> bool {
+        self.children.iter().all(|child| {
+            self.bounding_rect.contains_envelope(&child.bounding_rect) && child.bounds_consistent()
+        })
+    }
+
+    /// Folds the sorted hashes of the children, each covering its own
+    /// subtree at `depth`, into `hash`.
+    fn children_hash(&self, depth: usize, hash: u64) -> u64 {
+        let mut hashes: Vec<u64> = self
+            .children
+            .iter()
+            .map(|child| {
+                let [min_x, min_y] = child.bounding_rect.lower();
+                let [max_x, max_y] = child.bounding_rect.upper();
+                let mut hash = fnv1a(FNV_OFFSET, &(depth as u64).to_le_bytes());
+                for value in [child.area, min_x, min_y, max_x, max_y] {
+                    // -0.0 and 0.0 compare equal, so they hash the same.
+                    hash = fnv1a(hash, &(value + 0.0).to_bits().to_le_bytes());
+                }
+                child.children_hash(depth + 1, hash)
+            })
+            .collect();
+        hashes.sort_unstable();
+
+        let hash = fnv1a(hash, &(hashes.len() as u64).to_le_bytes());
+        hashes
+            .iter()
+            .fold(hash, |hash, child| fnv1a(hash, &child.to_le_bytes()))
+    }
+
+    /// Adds `center * area` and `area` of every descendant to
+    /// `[x, y, area]`, in `f64` so large trees do not lose precision.
+    fn weighted_sums(&self, sums: &mut [f64; 3]) {
+        for child in &self.children {
+            let area = child.area as f64;
+            sums[0] += child.center_point[0] as f64 * area;
+            sums[1] += child.center_point[1] as f64 * area;
+            sums[2] += area;
+            child.weighted_sums(sums);
+        }
+    }
+
+    fn count_where<F: Fn(&T) -> bool>(&self, pred: &F) -> usize {
+        self.children
+            .iter()
+            .map(|child| usize::from(pred(&child.value)) + child.count_where(pred))
+            .sum()
+    }
+
+    fn for_each_with_path<F: FnMut(&[usize], &T)>(&self, path: &mut Vec<usize>, f: &mut F) {
+        for (index, child) in self.children_sorted_by_area().into_iter().enumerate() {
+            path.push(index);
+            f(path, &child.value);
+            child.for_each_with_path(path, f);
+            path.pop();
+        }
+    }
+
+    fn fold_bottom_up<A, I, F>(&self, init: &I, combine: &F) -> FoldedNode<'_, T, A>
+    where
+        I: Fn(&T) -> A,
+        F: Fn(A, &A) -> A,
+    {
+        let children: Vec<FoldedNode<T, A>> = self
+            .children
+            .iter()
+            .map(|child| child.fold_bottom_up(init, combine))
+            .collect();
+        let acc = children
+            .iter()
+            .fold(init(&self.value), |acc, child| combine(acc, &child.acc));
+
+        FoldedNode {
+            value: &self.value,
+            acc,
+            children,
+        }
+    }
+
+    fn children_eq(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        self.children.size() == other.children.size()
+            && self
+                .children_sorted_by_area()
+                .into_iter()
+                .zip(other.children_sorted_by_area())
+                .all(|(l, r)| l.value == r.value && l.children_eq(r))
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn bounding_rect(&self) -> &AABB<AABBType> {
+        &self.bounding_rect
+    }
+
+    pub fn center_point(&self) -> [f32; 2] {
+        self.center_point
+    }
+
+    pub fn area(&self) -> f32 {
+        self.area
+    }
+
+    /// Mutable access to the value. Changing its geometry does not update
+    /// the node's cached bounds, center point or area.
+    pub fn value_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Union of this node's bounding rect and those of all its descendants.
+    /// Maintained on insertion, so this does not recurse.
+    pub fn bounding_rect_recursive(&self) -> ([f32; 2], [f32; 2]) {
+        (self.subtree_bounds.lower(), self.subtree_bounds.upper())
+    }
+
+    pub fn descendant_count(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| child.descendant_count() + 1)
+            .sum()
+    }
+
+    /// Depth of the shallowest value matching `pred`, relative to this node:
+    /// 0 for its own value, 1 for its direct children and so on. Searches
+    /// breadth first.
+    pub fn find_depth(&self, pred: impl Fn(&T) -> bool) -> Option<usize> {
+        let mut queue = VecDeque::from([(0, self)]);
+        while let Some((depth, node)) = queue.pop_front() {
+            if pred(&node.value) {
+                return Some(depth);
+            }
+            queue.extend(node.children.iter().map(|child| (depth + 1, child)));
+        }
+        None
+    }
+
+    /// Mutable iterator over the direct children. The R-tree indexes each
+    /// child by the bounds cached when it was inserted, so this is meant for
+    /// changing non-geometric parts of the values; moving or resizing a
+    /// shape through it leaves the index out of date.
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut TreeNode<T>> {
+        self.children.iter_mut()
+    }
+
+    /// Direct children ordered by `cmp` on their values. Children that
+    /// compare equal keep the R-tree's order, which can differ between
+    /// builds.
+    pub fn children_sorted(&self, cmp: impl Fn(&T, &T) -> Ordering) -> Vec<&TreeNode<T>> {
+        let mut children = self.children();
+        children.sort_by(|l, r| cmp(&l.value, &r.value));
+        children
+    }
+
+    /// Direct children by ascending area, then center point: the order
+    /// used by `PartialEq` and [`Tree::get_by_path`]. Unlike
+    /// [`TreeNode::children`], it does not depend on how the tree was built.
+    pub fn children_sorted_by_area(&self) -> Vec<&TreeNode<T>> {
+        let mut children = self.children();
+        children.sort_by(|l, r| {
+            l.area
+                .partial_cmp(&r.area)
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    l.center_point[0]
+                        .partial_cmp(&r.center_point[0])
+                        .unwrap_or(Ordering::Equal),
+                )
+                .then(
+                    l.center_point[1]
+                        .partial_cmp(&r.center_point[1])
+                        .unwrap_or(Ordering::Equal),
+                )
+        });
+        children
+    }
+
+    pub fn children(&self) -> Vec<&TreeNode<T>> {
+        let mut children = Vec::new();
+
+        for child in &self.children {
+            children.push(child);
+        }
+
+        children
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Selects the one node at the address `node` in an R-tree, descending only
+/// into parents whose envelope covers the node's `envelope`.
+struct SelectNode<T>
+where
+    T: Shape + Clone,
+{
+    node: *const TreeNode<T>,
+    envelope: AABB<AABBType>,
+}
+
+impl<T> SelectionFunction<TreeNode<T>> for SelectNode<T>
+where
+    T: Shape + Clone,
+{
+    fn should_unpack_parent(&self, envelope: &AABB<AABBType>) -> bool {
+        envelope.contains_envelope(&self.envelope)
+    }
+
+    fn should_unpack_leaf(&self, leaf: &TreeNode<T>) -> bool {
+        core::ptr::eq(leaf, self.node)
+    }
+}
+
+// MARK: ContainmentPolicy
+
+/// How to pick a parent when several siblings contain a new shape.
+#[derive(Debug, Clone, Default)]
+pub enum ContainmentPolicy<T> {
+    /// The first container found in R-tree order. This is what
+    /// [`TreeNode::add_node`] does.
+    FirstFound,
+    /// The container with the smallest area, then the smallest bounding rect.
+    #[default]
+    SmallestArea,
+    /// The container with the smallest bounding rect.
+    SmallestBounds,
+    /// The container that orders first (`Ordering::Less`) under the function.
+    Custom(fn(&T, &T) -> Ordering),
+}
+
+impl<T> ContainmentPolicy<T>
+where
+    T: Shape + Clone,
+{
+    /// Preferred candidate; ties go to the one found first.
+    fn choose<'a>(&self, candidates: Vec<&'a mut TreeNode<T>>) -> Option<&'a mut TreeNode<T>> {
+        match self {
+            ContainmentPolicy::FirstFound => candidates.into_iter().next(),
+            _ => candidates.into_iter().min_by(|l, r| self.compare(l, r)),
+        }
+    }
+
+    fn compare(&self, l: &TreeNode<T>, r: &TreeNode<T>) -> Ordering {
+        let by_bounds = || {
+            l.bounding_rect
+                .area()
+                .partial_cmp(&r.bounding_rect.area())
+                .unwrap_or(Ordering::Equal)
+        };
+
+        match self {
+            ContainmentPolicy::FirstFound => Ordering::Equal,
+            ContainmentPolicy::SmallestArea => l
+                .area
+                .partial_cmp(&r.area)
+                .unwrap_or(Ordering::Equal)
+                .then_with(by_bounds),
+            ContainmentPolicy::SmallestBounds => by_bounds(),
+            ContainmentPolicy::Custom(compare) => compare(&l.value, &r.value),
+        }
+    }
+}
+
+// MARK: InsertOutcome
+
+/// Result of [`Tree::try_insert`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InsertOutcome {
+    /// Inserted below an existing node with area `parent_area`.
+    Nested { parent_area: f32 },
+    /// Inserted directly below the root: no node contains it.
+    TopLevel,
+    /// Not inserted, because the value is [empty](Shape::is_empty).
+    Skipped,
+}
+
+// MARK: Pick
+
+/// Result of [`Tree::pick`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pick<T> {
+    /// The innermost value containing the point.
+    Inside(T),
+    /// The value nearest to a point outside every shape, and its distance.
+    Nearest(T, f32),
+}
+
+// MARK: TreeBuilder
+
+/// Collects construction options for a tree of `T`. Every option is off by
+/// default, which builds the same tree as `Tree::from((shapes, root))`
+/// after sorting the shapes by descending area.
+#[derive(Debug, Clone)]
+pub struct TreeBuilder<T> {
+    root: T,
+    dedupe: bool,
+    dedupe_epsilon: f32,
+    tolerance: f32,
+    policy: Option<ContainmentPolicy<T>>,
+    presorted: bool,
+}
+
+impl<T> TreeBuilder<T>
+where
+    T: Shape + Clone,
+{
+    /// Builder for a tree whose synthetic root holds `root`.
+    pub fn new(root: T) -> Self {
+        Self {
+            root,
+            dedupe: false,
+            dedupe_epsilon: 0.0,
+            tolerance: 0.0,
+            policy: None,
+            presorted: false,
+        }
+    }
+
+    /// Skip shapes identical to one already in the tree, as
+    /// [`Tree::insert_dedup`] does.
+    pub fn dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// With [`TreeBuilder::dedupe`], shapes whose areas and bounding rects
+    /// are this close count as identical. Does not affect containment.
+    pub fn dedupe_epsilon(mut self, epsilon: f32) -> Self {
+        self.dedupe_epsilon = epsilon;
+        self
+    }
+
+    /// Slack for containment: a shape nests in a container that holds it
+    /// within `tolerance` (see [`Shape::distance_to_point`]).
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Pick among overlapping containers with `policy` instead of taking the
+    /// first one found.
+    pub fn policy(mut self, policy: ContainmentPolicy<T>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Trust that the shapes are already sorted by descending area and
+    /// insert them as given.
+    pub fn presorted(mut self, presorted: bool) -> Self {
+        self.presorted = presorted;
+        self
+    }
+
+    pub fn build(self, shapes: Vec<T>) -> Tree<T> {
+        let mut nodes: Vec<TreeNode<T>> = shapes.into_iter().map(TreeNode::from).collect();
+        if !self.presorted {
+            nodes.sort_by(|l, r| r.area.partial_cmp(&l.area).unwrap_or(Ordering::Equal));
+        }
+        if !self.dedupe && self.policy.is_none() {
+            let capacity = nodes.len();
+            let top_level = Tree::top_level_of(nodes, capacity, self.tolerance);
+            return Tree::from_top_level(self.root, top_level);
+        }
+
+        let mut root = TreeNode::from(self.root);
+        root.subtree_bounds = AABB::new_empty();
+        for node in nodes {
+            if self.dedupe && root.find_duplicate(&node, self.dedupe_epsilon, true) {
+                continue;
+            }
+            match &self.policy {
+                Some(policy) => root.add_node_with_policy_within(node, policy, self.tolerance),
+                None => root.add_node_within(node, self.tolerance),
+            }
+        }
+        Tree {
+            root: Some(root),
+            capacity: 0,
+        }
+    }
+}
+
+impl<T> Default for TreeBuilder<T>
+where
+    T: Shape + Clone + Default,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// MARK: Entry
+
+/// Result of [`Tree::entry_at_point`], modeled on `HashMap`'s entry API.
+#[derive(Debug)]
+pub enum Entry<'a, T>
+where
+    T: Shape + Clone,
+{
+    Occupied(&'a mut T),
+    Vacant(VacantEntry<'a, T>),
+}
+
+#[derive(Debug)]
+pub struct VacantEntry<'a, T>
+where
+    T: Shape + Clone,
+{
+    tree: &'a mut Tree<T>,
+    point: [f32; 2],
+}
+
+impl<'a, T> Entry<'a, T>
+where
+    T: Shape + Clone,
+{
+    /// Value at the point, inserting `value` first if the entry is vacant.
+    /// Returns `None` only if the inserted value does not contain the point.
+    pub fn or_insert(self, value: T) -> Option<&'a mut T> {
+        match self {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+}
+
+impl<'a, T> VacantEntry<'a, T>
+where
+    T: Shape + Clone,
+{
+    pub fn point(&self) -> [f32; 2] {
+        self.point
+    }
+
+    /// Inserts `value` into the tree, returning it if it contains the point.
+    pub fn insert(self, value: T) -> Option<&'a mut T> {
+        let root = self.tree.root.as_mut()?;
+        root.add_node(value);
+
+        match self.tree.entry_at_point(self.point) {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant(_) => None,
+        }
+    }
+}
+
+// MARK: TreeDiff
+
+/// Result of [`Tree::diff_by_key`].
+#[derive(Debug, Clone)]
+pub struct TreeDiff<'a, T> {
+    /// Values only in the other tree.
+    pub added: Vec<&'a T>,
+    /// Values only in this tree.
+    pub removed: Vec<&'a T>,
+    /// Values in both trees whose parent changed, as `(before, after)`.
+    pub moved: Vec<(&'a T, &'a T)>,
+}
+
+// MARK: TreeSnapshot
+
+/// Immutable, reference-counted view of a tree, made by [`Tree::snapshot`].
+/// Clones share the same tree, and it is `Send` and `Sync` whenever `T` is,
+/// so it can be handed to reader threads while the original is edited. All
+/// `&self` queries are available through `Deref`.
+#[derive(Debug, Clone)]
+pub struct TreeSnapshot<T>
+where
+    T: Shape + Clone,
+{
+    tree: Arc<Tree<T>>,
+}
+
+impl<T> core::ops::Deref for TreeSnapshot<T>
+where
+    T: Shape + Clone,
+{
+    type Target = Tree<T>;
+
+    fn deref(&self) -> &Tree<T> {
+        &self.tree
+    }
+}
+
+// MARK: FoldedNode
+
+/// A node of the structure returned by [`Tree::fold_bottom_up`]: the original
+/// value, the accumulator for it and all of its descendants, and the folded
+/// children.
+#[derive(Debug, Clone)]
+pub struct FoldedNode<'a, T, A> {
+    pub value: &'a T,
+    pub acc: A,
+    pub children: Vec<FoldedNode<'a, T, A>>,
+}
+
+// MARK: Iterator
+
+#[derive(Debug, Clone)]
+pub struct TreeNodeDepthIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    order: VecDeque<(usize, &'a TreeNode<T>)>,
+}
+
+impl<'a, T> Iterator for TreeNodeDepthIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, tree_node) = self.order.pop_front()?;
+
+        for child in &tree_node.children {
+            self.order.push_back((depth + 1, child));
+        }
+
+        Some((depth, &tree_node.value))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeNodeDfsIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    stack: Vec<(usize, &'a TreeNode<T>)>,
+}
+
+impl<'a, T> TreeNodeDfsIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    /// Pushes the children of `node` so that they are popped in order.
+    fn push_children(&mut self, depth: usize, node: &'a TreeNode<T>) {
+        let start = self.stack.len();
+        self.stack
+            .extend(node.children.iter().map(|child| (depth, child)));
+        self.stack[start..].reverse();
+    }
+}
+
+impl<'a, T> Iterator for TreeNodeDfsIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, tree_node) = self.stack.pop()?;
+        self.push_children(depth + 1, tree_node);
+        Some((depth, &tree_node.value))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeNodeParentIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    order: VecDeque<(usize, Option<&'a T>, &'a TreeNode<T>)>,
+}
+
+impl<'a, T> Iterator for TreeNodeParentIterator<'a, T>
+where
+    T: Shape + Clone,
+{
+    type Item = (usize, Option<&'a T>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, parent, tree_node) = self.order.pop_front()?;
+
+        for child in &tree_node.children {
+            self.order
+                .push_back((depth + 1, Some(&tree_node.value), child));
+        }
+
+        Some((depth, parent, &tree_node.value))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeNodeDepthIntoIterator<T>
+where
+    T: Shape + Clone,
+{
+    order: VecDeque<(usize, TreeNode<T>)>,
+}
+
+impl<T> Iterator for TreeNodeDepthIntoIterator<T>
+where
+    T: Shape + Clone,
+{
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, tree_node) = self.order.pop_front()?;
+
+        for child in tree_node.children {
+            self.order.push_back((depth + 1, child));
+        }
+
+        Some((depth, tree_node.value))
+    }
+}
+
+impl<T> IntoIterator for Tree<T>
+where
+    T: Shape + Clone,
+{
+    type Item = (usize, T);
+
+    type IntoIter = TreeNodeDepthIntoIterator<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        if let Some(root) = self.root {
+            let mut queue = VecDeque::new();
+            for child in root.children {
+                queue.push_back((0, child));
+            }
+            TreeNodeDepthIntoIterator { order: queue }
+        } else {
+            TreeNodeDepthIntoIterator {
+                order: Default::default(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "geo-integration")]
+pub use geo_impls::TolerantPolygon;
+
+#[cfg(feature = "geo-integration")]
+mod geo_impls {
+    use crate::*;
+    use geo::{
+        AffineOps, AffineTransform, Area, Contains, Distance, Euclidean, Geometry, InteriorPoint,
+        Intersects, Length, LineString, Polygon,
+    };
+
+    /// Empty polygons have no bounding rect or interior point, and collinear
+    /// ones have no area to nest anything in. `from_polygon` and friends drop
+    /// them rather than building nodes with placeholder geometry.
+    fn has_area(polygon: &Polygon) -> bool {
+        polygon.unsigned_area() > 0.0
+    }
+
+    /// Root value for polygon trees.
+    fn empty_polygon() -> Polygon {
+        Polygon::new(geo::LineString::new(Vec::new()), Vec::new())
+    }
+
+    /// Corners of `rect`, or the origin for empty geometry, which trees never
+    /// store outside the synthetic root (see [`Shape::is_empty`]).
+    fn corners(rect: Option<geo::Rect>) -> ([f32; 2], [f32; 2]) {
+        match rect {
+            Some(rect) => {
+                let ((x1, y1), (x2, y2)) = (rect.min().x_y(), rect.max().x_y());
+                ([x1 as f32, y1 as f32], [x2 as f32, y2 as f32])
+            }
+            None => ([0.0, 0.0], [0.0, 0.0]),
+        }
+    }
+
+    /// `point`, or the middle of `rect` for geometry without an interior
+    /// point.
+    fn center_or_middle(point: Option<geo::Point>, rect: Option<geo::Rect>) -> [f32; 2] {
+        match point {
+            Some(point) => [point.x() as f32, point.y() as f32],
+            None => {
+                let (min, max) = corners(rect);
+                [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0]
+            }
+        }
+    }
+
+    /// The interior point, then the centers of the cells of a grid of about
+    /// `n` cells over the bounding rect that fall inside the polygon, up to
+    /// `n` points in all. Empty only for polygons without an interior.
+    fn sample_points(polygon: &Polygon, n: usize) -> Vec<geo::Point> {
+        let Some(first) = polygon.interior_point() else {
+            return Vec::new();
+        };
+        let mut points = vec![first];
+        let Some(rect) = geo::algorithm::bounding_rect::BoundingRect::bounding_rect(polygon) else {
+            return points;
+        };
+
+        let cells = (n as f64).sqrt().ceil() as usize;
+        for i in 0..cells {
+            for j in 0..cells {
+                if points.len() >= n {
+                    return points;
+                }
+                let point = geo::point! {
+                    x: rect.min().x + rect.width() * (i as f64 + 0.5) / cells as f64,
+                    y: rect.min().y + rect.height() * (j as f64 + 0.5) / cells as f64,
+                };
+                if polygon.contains(&point) {
+                    points.push(point);
+                }
+            }
+        }
+        points
+    }
+
+    fn geo_point([x, y]: [f32; 2]) -> geo::Point {
+        geo::point! {
+            x: x as f64,
+            y: y as f64,
+        }
+    }
+
+    fn affine([a, b, c, d, e, f]: [f32; 6]) -> AffineTransform {
+        AffineTransform::new(a as f64, c as f64, e as f64, b as f64, d as f64, f as f64)
+    }
+
+    impl Shape for Polygon {
+        fn contains_shape(&self, rhs: &Self) -> bool {
+            if self.unsigned_area() < rhs.unsigned_area() {
+                return false;
+            }
+
+            let samples = sample_points(rhs, super::CONTAINMENT_SAMPLES);
+            !samples.is_empty() && samples.iter().all(|point| self.contains(point))
+        }
+
+        /// The interior point plus the centers of the cells of a grid over the
+        /// bounding rect that fall inside the polygon.
+        fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+            sample_points(self, n)
+                .into_iter()
+                .map(|point| [point.x() as f32, point.y() as f32])
+                .collect()
+        }
+
+        fn contains_point(&self, point: [f32; 2]) -> bool {
+            self.contains(&geo::coord! {
+                x: point[0] as f64,
+                y: point[1] as f64,
+            })
+        }
+
+        fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+            Euclidean.distance(&geo_point(point), self) as f32
+        }
+
+        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+            corners(geo::algorithm::bounding_rect::BoundingRect::bounding_rect(
+                self,
+            ))
+        }
+
+        fn center_point(&self) -> [f32; 2] {
+            center_or_middle(
+                self.interior_point(),
+                geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self),
+            )
+        }
+
+        fn is_empty(&self) -> bool {
+            geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self).is_none()
+        }
+
+        fn area(&self) -> f32 {
+            self.unsigned_area() as f32
+        }
+
+        fn perimeter(&self) -> f32 {
+            let exterior = Euclidean.length(self.exterior());
+            let interiors: f64 = self
+                .interiors()
+                .iter()
+                .map(|ring| Euclidean.length(ring))
+                .sum();
+            (exterior + interiors) as f32
+        }
+
+        fn intersects(&self, other: &Self) -> bool {
+            Intersects::intersects(self, other)
+        }
+    }
+
+    impl Transform for Polygon {
+        fn transform(&self, matrix: [f32; 6]) -> Self {
+            self.affine_transform(&affine(matrix))
+        }
+    }
+
+    impl Tree<Polygon> {
+        /// Tree of `value` nested by containment. Empty and zero-area polygons
+        /// are skipped.
+        pub fn from_polygon(value: Vec<Polygon>) -> Self {
+            Self::build_polygons(value, TreeBuilder::new(empty_polygon()))
+        }
+
+        /// Like [`Tree::from_polygon`], dropping polygons identical to one
+        /// already inserted (see [`Tree::insert_dedup`]). Containment stays
+        /// exact; use [`TreeBuilder::tolerance`] to loosen it.
+        pub fn from_polygon_dedup(value: Vec<Polygon>, epsilon: f32) -> Self {
+            Self::build_polygons(
+                value,
+                TreeBuilder::new(empty_polygon())
+                    .dedupe(true)
+                    .dedupe_epsilon(epsilon),
+            )
+        }
+
+        pub fn from_polygon_with_policy(
+            value: Vec<Polygon>,
+            policy: &ContainmentPolicy<Polygon>,
+        ) -> Self {
+            Self::build_polygons(
+                value,
+                TreeBuilder::new(empty_polygon()).policy(policy.clone()),
+            )
+        }
+
+        /// Drops polygons without area and sorts the rest by their exact
+        /// area before handing them to `builder`.
+        fn build_polygons(mut value: Vec<Polygon>, builder: TreeBuilder<Polygon>) -> Self {
+            value.retain(has_area);
+            value.sort_by(|l, r| r.unsigned_area().partial_cmp(&l.unsigned_area()).unwrap());
+            builder.presorted(true).build(value)
+        }
+    }
+
+    /// Areal variants behave like their polygons. Points and lines have no
+    /// area, so they contain nothing and have no perimeter, but they can
+    /// still be nested inside areal shapes.
+    impl Shape for Geometry {
+        fn contains_shape(&self, rhs: &Self) -> bool {
+            let area = self.unsigned_area();
+            if area <= 0.0 || area < rhs.unsigned_area() {
+                return false;
+            }
+
+            rhs.interior_point()
+                .is_some_and(|point| self.contains(&point))
+        }
+
+        fn contains_point(&self, point: [f32; 2]) -> bool {
+            self.unsigned_area() > 0.0
+                && self.contains(&geo::coord! {
+                    x: point[0] as f64,
+                    y: point[1] as f64,
+                })
+        }
+
+        fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+            Euclidean.distance(&geo_point(point), self) as f32
+        }
+
+        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+            corners(geo::algorithm::bounding_rect::BoundingRect::bounding_rect(
+                self,
+            ))
+        }
+
+        fn center_point(&self) -> [f32; 2] {
+            center_or_middle(
+                self.interior_point(),
+                geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self),
+            )
+        }
+
+        fn is_empty(&self) -> bool {
+            geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self).is_none()
+        }
+
+        fn area(&self) -> f32 {
+            self.unsigned_area() as f32
+        }
+
+        fn perimeter(&self) -> f32 {
+            match self {
+                Geometry::Polygon(polygon) => polygon.perimeter(),
+                Geometry::MultiPolygon(polygons) => polygons.iter().map(Shape::perimeter).sum(),
+                Geometry::Rect(rect) => rect.to_polygon().perimeter(),
+                Geometry::Triangle(triangle) => triangle.to_polygon().perimeter(),
+                Geometry::GeometryCollection(geometries) => {
+                    geometries.iter().map(Shape::perimeter).sum()
+                }
+                _ => 0.0,
+            }
+        }
+
+        fn intersects(&self, other: &Self) -> bool {
+            Intersects::intersects(self, other)
+        }
+    }
+
+    impl Transform for Geometry {
+        fn transform(&self, matrix: [f32; 6]) -> Self {
+            self.affine_transform(&affine(matrix))
+        }
+    }
+
+    /// Open or closed lines as leaves: they have no area, so they contain
+    /// neither points nor shapes, but they nest inside the polygons around
+    /// them and can be found by their distance to a point, e.g. with
+    /// [`Tree::pick`]. To keep lines and polygons in one tree, wrap both in
+    /// [`Geometry`], whose line variants behave the same way.
+    impl Shape for LineString {
+        fn contains_shape(&self, _rhs: &Self) -> bool {
+            false
+        }
+
+        fn contains_point(&self, _point: [f32; 2]) -> bool {
+            false
+        }
+
+        fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+            Euclidean.distance(&geo_point(point), self) as f32
+        }
+
+        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+            corners(geo::algorithm::bounding_rect::BoundingRect::bounding_rect(
+                self,
+            ))
+        }
+
+        fn center_point(&self) -> [f32; 2] {
+            center_or_middle(
+                self.interior_point(),
+                geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self),
+            )
+        }
+
+        fn is_empty(&self) -> bool {
+            geo::algorithm::bounding_rect::BoundingRect::bounding_rect(self).is_none()
+        }
+
+        fn area(&self) -> f32 {
+            0.0
+        }
+
+        fn perimeter(&self) -> f32 {
+            0.0
+        }
+
+        fn intersects(&self, other: &Self) -> bool {
+            Intersects::intersects(self, other)
+        }
+    }
+
+    impl Transform for LineString {
+        fn transform(&self, matrix: [f32; 6]) -> Self {
+            self.affine_transform(&affine(matrix))
+        }
+    }
+
+    /// A polygon whose containment tests allow `epsilon` of slack, so shapes
+    /// that only fail to nest because of rounding at a shared boundary still
+    /// nest.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TolerantPolygon {
+        pub polygon: Polygon,
+        pub epsilon: f64,
+    }
+
+    impl TolerantPolygon {
+        fn within_epsilon(&self, point: geo::Point) -> bool {
+            Euclidean.distance(&point, &self.polygon) <= self.epsilon
+        }
+    }
+
+    impl Shape for TolerantPolygon {
+        /// Contained if every one of `rhs`'s sample points (see
+        /// [`Shape::interior_sample_points`]) is within `epsilon` of this
+        /// polygon and `rhs` is not larger.
+        fn contains_shape(&self, rhs: &Self) -> bool {
+            if self.polygon.unsigned_area() < rhs.polygon.unsigned_area() {
+                return false;
+            }
+
+            let samples = sample_points(&rhs.polygon, super::CONTAINMENT_SAMPLES);
+            !samples.is_empty() && samples.into_iter().all(|point| self.within_epsilon(point))
+        }
+
+        fn interior_sample_points(&self, n: usize) -> Vec<[f32; 2]> {
+            self.polygon.interior_sample_points(n)
+        }
+
+        fn contains_point(&self, point: [f32; 2]) -> bool {
+            self.within_epsilon(geo_point(point))
+        }
+
+        fn distance_to_point(&self, point: [f32; 2]) -> f32 {
+            let distance = Euclidean.distance(&geo_point(point), &self.polygon);
+            (distance - self.epsilon).max(0.0) as f32
+        }
+
+        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+            self.polygon.bounding_rect()
+        }
+
+        fn center_point(&self) -> [f32; 2] {
+            self.polygon.center_point()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.polygon.is_empty()
+        }
+
+        fn area(&self) -> f32 {
+            self.polygon.area()
+        }
+
+        fn perimeter(&self) -> f32 {
+            self.polygon.perimeter()
+        }
+
+        fn intersects(&self, other: &Self) -> bool {
+            Shape::intersects(&self.polygon, &other.polygon)
+        }
+    }
+
+    impl Transform for TolerantPolygon {
+        fn transform(&self, matrix: [f32; 6]) -> Self {
+            Self {
+                polygon: self.polygon.transform(matrix),
+                epsilon: self.epsilon,
+            }
+        }
+    }
+
+    impl Tree<TolerantPolygon> {
+        /// Like [`Tree::from_polygon`], treating shapes within `epsilon` of a
+        /// container as inside it.
+        pub fn from_polygon_with_tolerance(value: Vec<Polygon>, epsilon: f64) -> Self {
+            let mut value: Vec<TolerantPolygon> = value
+                .into_iter()
+                .filter(has_area)
+                .map(|polygon| TolerantPolygon { polygon, epsilon })
+                .collect();
+            value.sort_by(|l, r| {
+                r.polygon
+                    .unsigned_area()
+                    .partial_cmp(&l.polygon.unsigned_area())
+                    .unwrap()
+            });
+            let root = TolerantPolygon {
+                polygon: empty_polygon(),
+                epsilon,
+            };
+            TreeBuilder::new(root).presorted(true).build(value)
+        }
+    }
+
+    impl<T: Clone + Default> Tree<(T, Polygon)> {
+        pub fn from_polygon_id(mut value: Vec<(T, Polygon)>) -> Self {
+            value.retain(|(_, polygon)| has_area(polygon));
+            value.sort_by(|l, r| {
+                r.1.unsigned_area()
+                    .partial_cmp(&l.1.unsigned_area())
+                    .unwrap()
+            });
+            TreeBuilder::new((T::default(), empty_polygon()))
+                .presorted(true)
+                .build(value)
+        }
+
+        /// [`Tree::diff_by_key`] keyed by id.
+        pub fn diff<'a>(&'a self, other: &'a Self) -> TreeDiff<'a, (T, Polygon)>
+        where
+            T: Eq + std::hash::Hash,
+        {
+            self.diff_by_key(other, |(id, _)| id)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "geo-integration"))]
+mod tests {
+    use std::sync::Arc;
+
+    use geo::Polygon;
+
+    use crate::test_support::square;
+    use crate::*;
+
+    #[test]
+    #[cfg(feature = "svg-integration")]
+    #[allow(clippy::explicit_counter_loop)]
+    fn it_works() {
+        let path = std::path::Path::new("/home/cameron/Downloads/CAM.svg");
+        let lines = import_svg(path, 0.0001).unwrap();
+
+        let polygons: Vec<Polygon> = lines
+            .into_iter()
+            .map(|line| Polygon::new(line, Vec::new()))
+            .collect();
+
+        let tree: Tree<Polygon> = Tree::from_polygon(polygons);
+        let mut _count = 0;
+        for (_depth, _) in tree.iter() {
+            // println!("Depth: {}", depth);
+            _count += 1;
+        }
+
+        // println!("COUNT: {}", count);
+    }
+
+    #[test]
+    fn to_vec_matches_iter() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let snapshot = tree.to_vec();
+        let borrowed: Vec<(usize, Polygon)> = tree.iter().map(|(d, v)| (d, v.clone())).collect();
+
+        assert_eq!(snapshot, borrowed);
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot[2].0, 1);
+    }
+
+    #[test]
+    fn fold_bottom_up_sums_areas() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let mut folded = tree.fold_bottom_up(|p| p.area(), |acc, child| acc + child);
+        folded.sort_by(|l, r| r.acc.partial_cmp(&l.acc).unwrap());
+
+        assert_eq!(folded.len(), 2);
+        assert_eq!(folded[0].acc, 100.0 + 16.0 + 1.0);
+        assert_eq!(folded[0].children[0].acc, 16.0 + 1.0);
+        assert_eq!(folded[0].children[0].children[0].acc, 1.0);
+        assert_eq!(folded[1].acc, 25.0);
+    }
+
+    #[test]
+    fn would_parent_previews_insertion() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let tree = Tree::from_polygon(vec![outer.clone(), inner.clone()]);
+
+        assert_eq!(tree.would_parent(&square(2.0, 2.0, 1.0)), Some(&inner));
+        assert_eq!(tree.would_parent(&square(6.0, 6.0, 1.0)), Some(&outer));
+        assert_eq!(tree.would_parent(&square(20.0, 20.0, 1.0)), None);
+        assert_eq!(tree.iter().count(), 2);
+    }
+
+    #[test]
+    fn find_overlapping_siblings_uses_geometry() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(8.0, 8.0, 10.0),
+            square(30.0, 0.0, 5.0),
+        ]);
+
+        let overlapping = tree.find_overlapping_siblings();
+        assert_eq!(overlapping.len(), 1);
+
+        let triangle = Polygon::new(
+            geo::LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (0.0, 0.0)]),
+            Vec::new(),
+        );
+        let corner = square(0.9, 0.9, 1.0);
+        assert!(!Shape::intersects(&triangle, &corner));
+    }
+
+    #[test]
+    fn total_perimeter_includes_holes() {
+        let hole = square(2.0, 2.0, 2.0).exterior().clone();
+        let with_hole = Polygon::new(square(0.0, 0.0, 10.0).exterior().clone(), vec![hole]);
+        assert_eq!(with_hole.perimeter(), 40.0 + 8.0);
+
+        let tree = Tree::from_polygon(vec![with_hole, square(20.0, 0.0, 1.0)]);
+        assert_eq!(tree.total_perimeter(), 48.0 + 4.0);
+    }
+
+    #[test]
+    fn descendant_counts_per_node() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(6.0, 6.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let mut counts: Vec<(f32, usize)> = tree
+            .descendant_counts()
+            .into_iter()
+            .map(|(value, count)| (value.area(), count))
+            .collect();
+        counts.sort_by(|l, r| r.0.partial_cmp(&l.0).unwrap());
+        assert_eq!(
+            counts,
+            vec![(100.0, 3), (25.0, 0), (16.0, 1), (4.0, 0), (1.0, 0)]
+        );
+
+        let root = tree.root().as_ref().unwrap();
+        assert_eq!(root.descendant_count(), 5);
+    }
+
+    #[test]
+    fn siblings_at_point_uses_parent() {
+        let outer = square(0.0, 0.0, 10.0);
+        let left = square(1.0, 1.0, 3.0);
+        let right = square(6.0, 6.0, 3.0);
+        let other = square(20.0, 0.0, 5.0);
+        let tree = Tree::from_polygon(vec![
+            outer.clone(),
+            left.clone(),
+            right.clone(),
+            other.clone(),
+        ]);
+
+        assert_eq!(tree.siblings_at_point([2.0, 2.0]), vec![&right]);
+        assert_eq!(tree.siblings_at_point([21.0, 1.0]), vec![&outer]);
+        assert!(tree.siblings_at_point([50.0, 50.0]).is_empty());
+    }
+
+    #[test]
+    fn entry_at_point_inserts_when_vacant() {
+        let mut tree = Tree::from_polygon_id(vec![(1, square(0.0, 0.0, 10.0))]);
+
+        match tree.entry_at_point([20.5, 0.5]) {
+            Entry::Occupied(_) => panic!("nothing should contain the point"),
+            Entry::Vacant(entry) => {
+                let value = entry.insert((2, square(20.0, 0.0, 1.0))).unwrap();
+                value.0 = 3;
+            }
+        }
+
+        if let Entry::Occupied(value) = tree.entry_at_point([1.0, 1.0]) {
+            value.0 = 4;
+        }
+
+        let ids: Vec<i32> = tree.iter().map(|(_, (id, _))| *id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&3) && ids.contains(&4));
+
+        let value = tree
+            .entry_at_point([20.5, 0.5])
+            .or_insert((5, square(20.0, 0.0, 1.0)));
+        assert_eq!(value.unwrap().0, 3);
+    }
+
+    #[test]
+    fn shallow_clone_structure_shares_values() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let view = tree.shallow_clone_structure();
+        let depths: Vec<usize> = view.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(
+            depths,
+            tree.iter().map(|(depth, _)| depth).collect::<Vec<_>>()
+        );
+        assert_eq!(view.siblings_at_point([2.0, 2.0]).len(), 0);
+
+        let copy = view.clone();
+        for ((_, l), (_, r)) in view.iter().zip(copy.iter()) {
+            assert!(Arc::ptr_eq(l, r));
+        }
+
+        let shared = tree.into_shared();
+        assert_eq!(shared.iter().count(), 3);
+    }
+
+    #[test]
+    fn shape_forwards_through_pointers() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+
+        let borrowed = Tree::from((vec![&outer, &inner], &empty));
+        assert_eq!(
+            borrowed.would_parent(&&square(2.0, 2.0, 1.0)),
+            Some(&&inner)
+        );
+
+        let boxed = Tree::from((
+            vec![Box::new(outer.clone()), Box::new(inner.clone())],
+            Box::new(empty),
+        ));
+        let depths: Vec<usize> = boxed.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+        assert_eq!(Box::new(outer).perimeter(), 40.0);
+    }
+
+    #[test]
+    fn depth_at_point_counts_enclosing_shapes() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+        ]);
+
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 3);
+        assert_eq!(tree.depth_at_point([4.5, 4.5]), 2);
+        assert_eq!(tree.depth_at_point([8.0, 8.0]), 1);
+        assert_eq!(tree.depth_at_point([50.0, 50.0]), 0);
+    }
+
+    #[test]
+    fn is_inside_even_odd_alternates() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+        ]);
+
+        assert!(tree.is_inside_even_odd([8.0, 8.0]));
+        assert!(!tree.is_inside_even_odd([4.5, 4.5]));
+        assert!(tree.is_inside_even_odd([2.5, 2.5]));
+        assert!(!tree.is_inside_even_odd([50.0, 50.0]));
+    }
+
+    #[test]
+    fn bounding_rect_covers_all_shapes() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(20.0, -5.0, 5.0),
+        ]);
+        assert_eq!(tree.bounding_rect(), Some(([0.0, -5.0], [25.0, 10.0])));
+
+        let empty = Tree::from_polygon(Vec::new());
+        assert_eq!(empty.bounding_rect(), None);
+    }
+
+    #[test]
+    fn bounding_rect_recursive_tracks_descendants() {
+        let mut tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(20.0, 0.0, 5.0)]);
+        let root = tree.root.as_mut().unwrap();
+        root.add_node(square(30.0, 30.0, 1.0));
+        assert_eq!(root.bounding_rect_recursive(), ([0.0, 0.0], [31.0, 31.0]));
+
+        let outer = root
+            .children()
+            .into_iter()
+            .find(|c| c.area == 100.0)
+            .unwrap();
+        assert_eq!(outer.bounding_rect_recursive(), ([0.0, 0.0], [10.0, 10.0]));
+        assert_eq!(tree.bounding_rect(), Some(([0.0, 0.0], [31.0, 31.0])));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter_matches_iter() {
+        use rayon::iter::ParallelIterator;
+
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let mut parallel: Vec<(usize, f32)> = tree
+            .par_iter()
+            .map(|(depth, value)| (depth, value.area()))
+            .collect();
+        parallel.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        let mut serial: Vec<(usize, f32)> = tree
+            .iter()
+            .map(|(depth, value)| (depth, value.area()))
+            .collect();
+        serial.sort_by(|l, r| l.partial_cmp(r).unwrap());
+
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn drain_empties_and_keeps_tree_usable() {
+        let mut tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(1.0, 1.0, 4.0)]);
+
+        let drained: Vec<(usize, Polygon)> = tree.drain().collect();
+        assert_eq!(drained.len(), 2);
+        assert_eq!(drained[1].0, 1);
+        assert!(tree.is_empty());
+        assert_eq!(tree.bounding_rect(), None);
+
+        for (_, polygon) in drained {
+            tree.insert(polygon);
+        }
+        assert!(!tree.is_empty());
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn containment_policy_breaks_equal_area_ties() {
+        let triangle = Polygon::new(
+            geo::LineString::from(vec![(9.0, 1.0), (29.0, 1.0), (29.0, 11.0), (9.0, 1.0)]),
+            Vec::new(),
+        );
+        let values = vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, triangle),
+            (3, square(9.6, 1.05, 0.1)),
+        ];
+        let root = (
+            0,
+            Polygon::new(geo::LineString::new(Vec::new()), Vec::new()),
+        );
+        let parent_of_3 = |tree: &Tree<(i32, Polygon)>| {
+            tree.descendant_counts()
+                .into_iter()
+                .find(|((_, polygon), count)| polygon.area() == 100.0 && *count == 1)
+                .map(|((id, _), _)| *id)
+        };
+
+        let tree = Tree::from_with_policy(values.clone(), root.clone(), &Default::default());
+        assert_eq!(parent_of_3(&tree), Some(1));
+
+        let tree = Tree::from_with_policy(
+            values.clone(),
+            root.clone(),
+            &ContainmentPolicy::SmallestBounds,
+        );
+        assert_eq!(parent_of_3(&tree), Some(1));
+
+        let tree = Tree::from_with_policy(
+            values,
+            root,
+            &ContainmentPolicy::Custom(|l, r| r.0.cmp(&l.0)),
+        );
+        assert_eq!(parent_of_3(&tree), Some(2));
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct Rect([f32; 2], [f32; 2]);
+
+    impl Shape for Rect {
+        fn contains_point(&self, point: [f32; 2]) -> bool {
+            (self.0[0]..=self.1[0]).contains(&point[0])
+                && (self.0[1]..=self.1[1]).contains(&point[1])
+        }
+
+        fn bounding_rect(&self) -> ([f32; 2], [f32; 2]) {
+            (self.0, self.1)
+        }
+
+        fn center_point(&self) -> [f32; 2] {
+            [(self.0[0] + self.1[0]) / 2.0, (self.0[1] + self.1[1]) / 2.0]
+        }
+
+        fn area(&self) -> f32 {
+            (self.1[0] - self.0[0]) * (self.1[1] - self.0[1])
+        }
+    }
+
+    #[test]
+    fn default_contains_shape_uses_bounds_and_center() {
+        let outer = Rect([0.0, 0.0], [10.0, 10.0]);
+        assert!(outer.contains_shape(&Rect([1.0, 1.0], [4.0, 4.0])));
+        assert!(!outer.contains_shape(&Rect([8.0, 8.0], [12.0, 12.0])));
+
+        let tree = Tree::from((
+            vec![outer, Rect([1.0, 1.0], [4.0, 4.0])],
+            Rect([0.0, 0.0], [0.0, 0.0]),
+        ));
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn from_iter_streaming_settles_late_containers() {
+        let tree = Tree::from_iter_streaming(vec![
+            Rect([1.0, 1.0], [2.0, 2.0]),
+            Rect([1.0, 1.0], [4.0, 4.0]),
+            Rect([6.0, 6.0], [8.0, 8.0]),
+            Rect([0.0, 0.0], [10.0, 10.0]),
+            Rect([20.0, 0.0], [25.0, 5.0]),
+        ]);
+
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 0, 1, 1, 2]);
+        assert_eq!(tree.depth_at_point([1.5, 1.5]), 3);
+    }
+
+    #[test]
+    fn rebuild_fixes_late_containers() {
+        let mut tree = Tree::from_polygon(vec![square(2.0, 2.0, 1.0)]);
+        tree.insert(square(1.0, 1.0, 4.0));
+        tree.insert(square(0.0, 0.0, 10.0));
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 1);
+
+        tree.rebuild();
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 3);
+        assert_eq!(tree.iter().count(), 3);
+    }
+
+    #[test]
+    fn within_distance_uses_centers_and_containment() {
+        let outer = square(0.0, 0.0, 100.0);
+        let left = square(10.0, 10.0, 2.0);
+        let right = square(14.0, 10.0, 2.0);
+        let far = square(80.0, 80.0, 2.0);
+        let tree = Tree::from_polygon(vec![outer.clone(), left.clone(), right.clone(), far]);
+
+        let mut found = tree.within_distance([13.0, 11.0], 4.5);
+        found.sort_by(|l, r| {
+            l.center_point()[0]
+                .partial_cmp(&r.center_point()[0])
+                .unwrap()
+        });
+        assert_eq!(found, vec![&left, &right, &outer]);
+
+        assert_eq!(tree.within_distance([13.0, 11.0], 0.5), vec![&outer]);
+        assert!(tree.within_distance([200.0, 200.0], 1.0).is_empty());
+    }
+
+    #[test]
+    fn k_nearest_orders_by_distance() {
+        let outer = square(0.0, 0.0, 100.0);
+        let left = square(10.0, 10.0, 2.0);
+        let right = square(14.0, 10.0, 2.0);
+        let far = square(80.0, 80.0, 2.0);
+        let tree = Tree::from_polygon(vec![
+            outer.clone(),
+            left.clone(),
+            right.clone(),
+            far.clone(),
+        ]);
+
+        assert_eq!(tree.k_nearest([12.5, 11.0], 2), vec![&outer, &left]);
+        assert_eq!(
+            tree.k_nearest([12.5, 11.0], 10),
+            vec![&outer, &left, &right, &far]
+        );
+        assert!(tree.k_nearest([12.5, 11.0], 0).is_empty());
+        assert_eq!(tree.k_nearest([f32::NAN, 11.0], 2).len(), 2);
+    }
+
+    #[test]
+    fn iter_with_parent_pairs_nodes() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let innermost = square(2.0, 2.0, 1.0);
+        let tree = Tree::from_polygon(vec![outer.clone(), inner.clone(), innermost.clone()]);
+
+        let pairs: Vec<(usize, Option<&Polygon>, &Polygon)> = tree.iter_with_parent().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (0, None, &outer),
+                (1, Some(&outer), &inner),
+                (2, Some(&inner), &innermost),
+            ]
+        );
+    }
+
+    #[test]
+    fn enclosing_is_innermost_first() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let tree = Tree::from_polygon(vec![
+            outer.clone(),
+            inner.clone(),
+            square(6.0, 6.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        assert_eq!(tree.enclosing(&square(2.0, 2.0, 1.0)), vec![&inner, &outer]);
+        assert_eq!(tree.enclosing(&square(4.0, 4.0, 3.0)), vec![&outer]);
+        assert!(tree.enclosing(&square(8.0, 8.0, 5.0)).is_empty());
+    }
+
+    #[test]
+    fn contained_within_selects_fully_covered() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let innermost = square(2.0, 2.0, 1.0);
+        let partial = square(6.0, 6.0, 3.0);
+        let tree = Tree::from_polygon(vec![
+            outer,
+            inner.clone(),
+            innermost.clone(),
+            partial,
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let selection = square(0.5, 0.5, 7.0);
+        assert_eq!(tree.contained_within(&selection), vec![&inner, &innermost]);
+        assert!(tree.contained_within(&square(40.0, 40.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn children_mut_updates_values() {
+        fn retag(node: &mut TreeNode<(i32, Polygon)>) {
+            for child in node.children_mut() {
+                child.value_mut().0 += 10;
+                retag(child);
+            }
+        }
+
+        let mut tree = Tree::from_polygon_id(vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, square(1.0, 1.0, 4.0)),
+            (3, square(20.0, 0.0, 5.0)),
+        ]);
+        retag(tree.root_mut().unwrap());
+
+        let mut ids: Vec<i32> = tree.iter().map(|(_, (id, _))| *id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![11, 12, 13]);
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn trees_compare_structurally() {
+        let shapes = vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(6.0, 6.0, 2.0),
+            square(20.0, 0.0, 5.0),
+            square(30.0, 0.0, 5.0),
+        ];
+        let tree = Tree::from_polygon(shapes.clone());
+
+        let mut reversed = shapes.clone();
+        reversed.reverse();
+        assert_eq!(tree, Tree::from_polygon(reversed));
+
+        let mut flat = Tree::from_polygon(Vec::new());
+        for shape in shapes.into_iter().rev() {
+            flat.insert(shape);
+        }
+        assert_ne!(tree, flat);
+        assert_eq!(
+            Tree::from_polygon(Vec::new()),
+            Tree::from_polygon(Vec::new())
+        );
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_moved() {
+        let before = Tree::from_polygon_id(vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, square(20.0, 0.0, 10.0)),
+            (3, square(1.0, 1.0, 2.0)),
+            (4, square(5.0, 5.0, 2.0)),
+        ]);
+        let after = Tree::from_polygon_id(vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, square(20.0, 0.0, 10.0)),
+            (3, square(21.0, 1.0, 2.0)),
+            (5, square(25.0, 5.0, 2.0)),
+        ]);
+
+        let diff = before.diff(&after);
+        let ids = |values: &[&(i32, Polygon)]| values.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added), vec![5]);
+        assert_eq!(ids(&diff.removed), vec![4]);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(diff.moved[0].0.1, square(1.0, 1.0, 2.0));
+        assert_eq!(diff.moved[0].1.1, square(21.0, 1.0, 2.0));
+
+        assert!(before.diff(&before).moved.is_empty());
+    }
+
+    #[test]
+    fn get_by_path_uses_canonical_order() {
+        let outer = square(0.0, 0.0, 10.0);
+        let small = square(1.0, 1.0, 2.0);
+        let large = square(5.0, 5.0, 4.0);
+        let tree = Tree::from_polygon(vec![
+            square(20.0, 0.0, 20.0),
+            outer.clone(),
+            large.clone(),
+            small.clone(),
+        ]);
+
+        assert_eq!(tree.get_by_path(&[0]), Some(&outer));
+        assert_eq!(tree.get_by_path(&[0, 0]), Some(&small));
+        assert_eq!(tree.get_by_path(&[0, 1]), Some(&large));
+        assert_eq!(tree.get_by_path(&[0, 2]), None);
+        assert_eq!(tree.get_by_path(&[]), None);
+    }
+
+    #[test]
+    fn tree_is_send_and_sync() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<Tree<Polygon>>();
+        assert_send_sync::<Tree<Arc<Polygon>>>();
+        assert_send_sync::<TreeNodeDepthIterator<Polygon>>();
+        assert_send_sync::<TreeNodeDepthIntoIterator<Polygon>>();
+
+        let tree = Arc::new(Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+        ]));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let tree = Arc::clone(&tree);
+                std::thread::spawn(move || tree.depth_at_point([2.0, 2.0]))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn to_rtree_contains_every_node() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let flat = tree.to_rtree();
+        assert_eq!(flat.size(), 4);
+        for (_, value) in tree.iter() {
+            assert!(flat.iter().any(|node| node.value() == value));
+        }
+        assert!(flat.iter().all(|node| node.children().is_empty()));
+        assert_eq!(flat.locate_all_at_point(&[2.5, 2.5]).count(), 3);
+    }
+
+    #[test]
+    fn tolerance_nests_shapes_on_shared_edge() {
+        let outer = square(0.0, 0.0, 10.0);
+        let sliver = Polygon::new(
+            geo::LineString::from(vec![
+                (2.0, 9.99),
+                (8.0, 9.99),
+                (8.0, 10.01),
+                (2.0, 10.01),
+                (2.0, 9.99),
+            ]),
+            Vec::new(),
+        );
+
+        let strict = Tree::from_polygon(vec![outer.clone(), sliver.clone()]);
+        assert_eq!(strict.iter().filter(|(depth, _)| *depth == 1).count(), 0);
+
+        let tolerant = Tree::from_polygon_with_tolerance(vec![outer.clone(), sliver], 0.02);
+        assert_eq!(tolerant.iter().filter(|(depth, _)| *depth == 1).count(), 1);
+
+        // Its interior point lies on the outer edge, but most of it is outside.
+        let overlapping = Polygon::new(
+            geo::LineString::from(vec![
+                (6.0, 2.0),
+                (14.0, 2.0),
+                (14.0, 4.0),
+                (6.0, 4.0),
+                (6.0, 2.0),
+            ]),
+            Vec::new(),
+        );
+        let tolerant = Tree::from_polygon_with_tolerance(vec![outer, overlapping], 0.02);
+        assert_eq!(tolerant.iter().filter(|(depth, _)| *depth == 1).count(), 0);
+    }
+
+    #[test]
+    fn shrink_to_fit_preserves_structure() {
+        /// Parent nodes of the R-tree `rtree`.
+        fn rtree_nodes(rtree: &rstar::RTree<TreeNode<Polygon>>) -> usize {
+            let mut count = 0;
+            let mut stack = vec![rtree.root()];
+            while let Some(parent) = stack.pop() {
+                count += 1;
+                stack.extend(parent.children().iter().filter_map(|child| match child {
+                    rstar::RTreeNode::Parent(parent) => Some(parent),
+                    rstar::RTreeNode::Leaf(_) => None,
+                }));
+            }
+            count
+        }
+
+        let mut tree = Tree::from_polygon(Vec::new());
+        for i in 0..100 {
+            let x = i as f64 * 20.0;
+            tree.insert(square(x, 0.0, 10.0));
+            tree.insert(square(x + 1.0, 1.0, 4.0));
+        }
+        let before = tree.clone();
+        let top_level = &tree.root.as_ref().unwrap().children;
+        let incremental = rtree_nodes(top_level);
+        let bulk_loaded = rtree_nodes(&rstar::RTree::bulk_load(
+            top_level.iter().cloned().collect(),
+        ));
+        assert_ne!(incremental, bulk_loaded);
+
+        tree.shrink_to_fit();
+        assert_eq!(
+            rtree_nodes(&tree.root.as_ref().unwrap().children),
+            bulk_loaded
+        );
+        assert_eq!(tree, before);
+        assert_eq!(tree.depth_at_point([981.5, 1.5]), 2);
+        assert_eq!(
+            tree.k_nearest([500.0, 5.0], 1),
+            before.k_nearest([500.0, 5.0], 1)
+        );
+    }
+
+    #[test]
+    fn visit_balances_enter_and_leave() {
+        struct Recorder(Vec<(bool, usize, f32)>);
+
+        impl TreeVisitor<Polygon> for Recorder {
+            fn enter(&mut self, depth: usize, value: &Polygon) {
+                self.0.push((true, depth, value.area()));
+            }
+
+            fn leave(&mut self, depth: usize, value: &Polygon) {
+                self.0.push((false, depth, value.area()));
+            }
+        }
+
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+        ]);
+        let mut recorder = Recorder(Vec::new());
+        tree.visit(&mut recorder);
+
+        assert_eq!(
+            recorder.0,
+            vec![
+                (true, 0, 100.0),
+                (true, 1, 16.0),
+                (true, 2, 1.0),
+                (false, 2, 1.0),
+                (false, 1, 16.0),
+                (false, 0, 100.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn geometry_tree_mixes_variants() {
+        use geo::{Geometry, Rect};
+
+        let empty = Geometry::Polygon(Polygon::new(geo::LineString::new(Vec::new()), Vec::new()));
+        let tree = Tree::from((
+            vec![
+                Geometry::Rect(Rect::new((0.0, 0.0), (10.0, 10.0))),
+                Geometry::Polygon(square(1.0, 1.0, 4.0)),
+                Geometry::Rect(Rect::new((2.0, 2.0), (3.0, 3.0))),
+                Geometry::Point(geo::Point::new(7.0, 7.0)),
+            ],
+            empty,
+        ));
+
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1, 1, 2]);
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 3);
+        assert_eq!(tree.total_perimeter(), 40.0 + 16.0 + 4.0);
+    }
+
+    #[test]
+    fn area_bounds_over_all_depths() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 0.5),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        assert_eq!(tree.area_bounds(), Some((0.25, 100.0)));
+        assert_eq!(tree.min_area(), Some(0.25));
+        assert_eq!(tree.max_area(), Some(100.0));
+        assert_eq!(Tree::from_polygon(Vec::new()).area_bounds(), None);
+    }
+
+    #[test]
+    fn direct_child_count_at_point_counts_one_level() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 20.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(8.0, 8.0, 4.0),
+            square(14.0, 14.0, 4.0),
+        ]);
+
+        assert_eq!(tree.direct_child_count_at_point([19.0, 1.0]), Some(3));
+        assert_eq!(tree.direct_child_count_at_point([4.5, 4.5]), Some(1));
+        assert_eq!(tree.direct_child_count_at_point([2.5, 2.5]), Some(0));
+        assert_eq!(tree.direct_child_count_at_point([50.0, 50.0]), None);
+    }
+
+    #[test]
+    fn from_root_keeps_given_structure() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 4.0);
+        let other = square(20.0, 0.0, 5.0);
+        let root = TreeNode::with_children(
+            square(-100.0, -100.0, 1.0),
+            vec![
+                TreeNode::with_children(outer.clone(), vec![TreeNode::from(inner.clone())]),
+                TreeNode::from(other.clone()),
+            ],
+        );
+        let tree = Tree::from_root(root);
+
+        assert_eq!(tree, Tree::from_polygon(vec![outer, inner, other]));
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+        assert_eq!(tree.bounding_rect(), Some(([0.0, 0.0], [25.0, 10.0])));
+    }
+
+    #[test]
+    fn tree_node_exposes_cached_geometry() {
+        let node = TreeNode::from(square(1.0, 2.0, 4.0));
+
+        assert_eq!(node.area(), 16.0);
+        assert_eq!(node.center_point(), [3.0, 4.0]);
+        assert_eq!(node.bounding_rect().lower(), [1.0, 2.0]);
+        assert_eq!(node.bounding_rect().upper(), [5.0, 6.0]);
+    }
+
+    #[test]
+    fn into_top_level_nodes_moves_subtrees() {
+        let source = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+        ]);
+        let mut target = Tree::from_polygon(vec![square(-10.0, -10.0, 50.0)]);
+
+        let nodes = source.into_top_level_nodes();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].descendant_count(), 2);
+        for node in nodes {
+            target.insert(node);
+        }
+
+        assert_eq!(target.iter().count(), 4);
+        assert_eq!(target.depth_at_point([2.5, 2.5]), 4);
+    }
+
+    #[test]
+    fn split_by_top_level_partitions_nodes() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(20.0, 0.0, 5.0),
+            square(21.0, 1.0, 1.0),
+            square(40.0, 0.0, 5.0),
+        ]);
+        let mut original: Vec<(usize, f32)> = tree
+            .iter()
+            .map(|(depth, value)| (depth, value.area()))
+            .collect();
+
+        let parts = tree.split_by_top_level();
+        assert_eq!(parts.len(), 3);
+
+        let mut union: Vec<(usize, f32)> = parts
+            .iter()
+            .flat_map(|part| part.iter().map(|(depth, value)| (depth, value.area())))
+            .collect();
+        original.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        union.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(union, original);
+    }
+
+    #[test]
+    fn dedup_drops_identical_polygons() {
+        let tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(0.0, 0.0, 10.0)]);
+        assert_eq!(tree.iter().count(), 2);
+
+        let tree = Tree::from_polygon_dedup(
+            vec![
+                square(0.0, 0.0, 10.0),
+                square(1.0, 1.0, 4.0),
+                square(0.0, 0.0, 10.0),
+                square(1.0, 1.0, 4.0),
+                square(20.0, 0.0, 5.0),
+            ],
+            0.001,
+        );
+        assert_eq!(tree.iter().count(), 3);
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn collapse_redundant_promotes_sole_child() {
+        let mut tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(0.1, 0.1, 9.8),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 2.0),
+            square(20.0, 0.0, 8.0),
+            square(21.0, 1.0, 2.0),
+        ]);
+        tree.collapse_redundant(|parent, child| child.area() / parent.area() > 0.9);
+
+        let areas: Vec<(usize, f32)> = tree
+            .iter()
+            .map(|(depth, polygon)| (depth, polygon.area().round()))
+            .collect();
+        assert_eq!(areas.len(), 5);
+        assert!(areas.contains(&(0, 96.0)));
+        assert!(!areas.contains(&(0, 100.0)));
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+        assert_eq!(tree.depth_at_point([22.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn transform_moves_whole_tree() {
+        let mut tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+        let before: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+
+        tree.transform([2.0, 0.0, 0.0, 2.0, 100.0, 50.0]);
+
+        let after: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(before, after);
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 0);
+        assert_eq!(tree.depth_at_point([105.0, 55.0]), 3);
+        assert_eq!(tree.bounding_rect(), Some(([100.0, 50.0], [150.0, 70.0])));
+
+        let inner = tree.get_by_path(&[1, 0]).unwrap();
+        assert_eq!(inner.area(), 64.0);
+        tree.rebuild();
+        assert_eq!(
+            tree.iter().map(|(depth, _)| depth).collect::<Vec<_>>(),
+            before
+        );
+    }
+
+    #[test]
+    fn empty_geometry_is_skipped_on_every_insertion_path() {
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+        let values = vec![square(5.0, 5.0, 2.0), empty.clone()];
+
+        let mut tree = Tree::from((values.clone(), empty.clone()));
+        tree.insert(empty.clone());
+        tree.insert_with_policy(empty.clone(), &ContainmentPolicy::SmallestArea);
+        assert_eq!(tree.try_insert(empty.clone()), InsertOutcome::Skipped);
+        assert!(!tree.insert_dedup(empty.clone(), 0.0));
+        tree.extend([empty.clone()]);
+        assert_eq!(tree.iter().count(), 1);
+        assert_eq!(tree.bounding_rect(), Some(([5.0, 5.0], [7.0, 7.0])));
+
+        let built = TreeBuilder::new(empty.clone()).build(values.clone());
+        assert_eq!(built.iter().count(), 1);
+        let policy = TreeBuilder::new(empty.clone())
+            .policy(ContainmentPolicy::SmallestArea)
+            .build(values.clone());
+        assert_eq!(policy.iter().count(), 1);
+
+        let mut extended = Tree::with_capacity(empty.clone(), 2);
+        extended.extend(values);
+        assert_eq!(extended.bounding_rect(), Some(([5.0, 5.0], [7.0, 7.0])));
+    }
+
+    #[test]
+    fn try_insert_reports_parent() {
+        let mut tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(1.0, 1.0, 4.0)]);
+
+        assert_eq!(
+            tree.try_insert(square(2.0, 2.0, 1.0)),
+            InsertOutcome::Nested { parent_area: 16.0 }
+        );
+        assert_eq!(
+            tree.try_insert(square(6.0, 6.0, 1.0)),
+            InsertOutcome::Nested { parent_area: 100.0 }
+        );
+        assert_eq!(
+            tree.try_insert(square(50.0, 50.0, 1.0)),
+            InsertOutcome::TopLevel
+        );
+        assert_eq!(tree.iter().count(), 5);
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 3);
+    }
+
+    #[test]
+    fn degenerate_polygons_are_skipped() {
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+        let collinear = Polygon::new(
+            geo::LineString::from(vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (1.0, 1.0)]),
+            Vec::new(),
+        );
+        assert_eq!(empty.bounding_rect(), ([0.0, 0.0], [0.0, 0.0]));
+        assert_eq!(empty.center_point(), [0.0, 0.0]);
+
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            empty.clone(),
+            collinear,
+            square(1.0, 1.0, 4.0),
+        ]);
+        assert_eq!(tree.iter().count(), 2);
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+
+        let tree = Tree::from_polygon_id(vec![(1, empty), (2, square(0.0, 0.0, 1.0))]);
+        assert_eq!(tree.iter().count(), 1);
+    }
+
+    #[test]
+    fn for_each_with_path_numbers_nodes() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let mut paths = Vec::new();
+        tree.for_each_with_path(|path, polygon| {
+            let path: Vec<String> = path.iter().map(usize::to_string).collect();
+            paths.push((path.join("/"), polygon.area()));
+        });
+        assert_eq!(
+            paths,
+            vec![
+                ("0".to_string(), 25.0),
+                ("1".to_string(), 100.0),
+                ("1/0".to_string(), 4.0),
+                ("1/1".to_string(), 16.0),
+                ("1/1/0".to_string(), 1.0),
+            ]
+        );
+
+        tree.for_each_with_path(|path, polygon| {
+            assert_eq!(tree.get_by_path(path), Some(polygon));
+        });
+    }
+
+    #[test]
+    fn root_is_never_yielded() {
+        let tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(1.0, 1.0, 2.0)]);
+        assert_eq!(tree.iter().count(), 2);
+        assert_eq!(tree.iter().next().unwrap().0, 0);
+        assert_eq!(tree.root().as_ref().unwrap().area(), 0.0);
+        assert!(!tree.root_contains_point([1.5, 1.5]));
+
+        let tree = Tree::with_root(
+            square(-50.0, -50.0, 100.0),
+            vec![square(1.0, 1.0, 2.0), square(0.0, 0.0, 10.0)],
+        );
+        let root = tree.root().as_ref().unwrap();
+        assert_eq!(root.area(), 10000.0);
+        assert_eq!(root.bounding_rect().lower(), [-50.0, -50.0]);
+        assert_eq!(tree.bounding_rect(), Some(([0.0, 0.0], [10.0, 10.0])));
+        assert_eq!(tree.iter().count(), 2);
+        assert_eq!(tree.depth_at_point([1.5, 1.5]), 2);
+        assert!(tree.root_contains_point([-40.0, -40.0]));
+        assert_eq!(tree.depth_at_point([-40.0, -40.0]), 0);
+    }
+
+    #[test]
+    fn tagged_shapes_nest_by_shape() {
+        let tree = Tree::from((
+            vec![
+                ("outer", Rect([0.0, 0.0], [10.0, 10.0])),
+                ("inner", Rect([1.0, 1.0], [4.0, 4.0])),
+            ],
+            ("root", Rect::default()),
+        ));
+
+        assert_eq!(tree.depth_at_point([2.0, 2.0]), 2);
+        let tags: Vec<(usize, &str)> = tree.iter().map(|(depth, (tag, _))| (depth, *tag)).collect();
+        assert_eq!(tags, vec![(0, "outer"), (1, "inner")]);
+    }
+
+    #[test]
+    fn count_where_counts_matches() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let large = tree.count_where(|polygon| polygon.area() >= 16.0);
+        let small = tree.count_where(|polygon| polygon.area() < 16.0);
+        assert_eq!((large, small), (3, 2));
+        assert_eq!(large + small, tree.len());
+        assert_eq!(tree.len(), tree.iter().count());
+        assert_eq!(Tree::from_polygon(Vec::new()).len(), 0);
+    }
+
+    #[test]
+    fn levels_group_by_depth() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let levels = tree.levels();
+        let sizes: Vec<usize> = levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert_eq!(sizes.iter().sum::<usize>(), tree.len());
+        assert_eq!(levels[2][0].area(), 1.0);
+        assert!(Tree::from_polygon(Vec::new()).levels().is_empty());
+    }
+
+    #[test]
+    fn weighted_centroid_of_symmetric_layout() {
+        let tree = Tree::from_polygon(vec![
+            square(-5.0, -5.0, 10.0),
+            square(-1.0, -1.0, 2.0),
+            square(20.0, -1.0, 2.0),
+            square(-22.0, -1.0, 2.0),
+        ]);
+        assert_eq!(tree.weighted_centroid(), Some([0.0, 0.0]));
+
+        let tree = Tree::from_polygon(vec![square(0.0, 0.0, 2.0), square(10.0, 0.0, 4.0)]);
+        let [x, y] = tree.weighted_centroid().unwrap();
+        assert!((x - (4.0 + 16.0 * 12.0) / 20.0).abs() < 1e-5);
+        assert!((y - (4.0 + 16.0 * 2.0) / 20.0).abs() < 1e-5);
+
+        assert_eq!(Tree::from_polygon(Vec::new()).weighted_centroid(), None);
+    }
+
+    #[test]
+    fn snapshot_survives_mutation() {
+        let mut tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(1.0, 1.0, 2.0)]);
+        let snapshot = tree.snapshot();
+
+        let reader = {
+            let snapshot = snapshot.clone();
+            std::thread::spawn(move || {
+                (
+                    snapshot.len(),
+                    snapshot.deepest_at_point([1.5, 1.5]).map(Shape::area),
+                )
+            })
+        };
+
+        tree.insert(square(1.2, 1.2, 0.5));
+        tree.drain().for_each(drop);
+
+        assert_eq!(reader.join().unwrap(), (2, Some(4.0)));
+        assert_eq!(snapshot.iter().count(), 2);
+        assert_eq!(snapshot.depth_at_point([1.5, 1.5]), 2);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn annotate_keeps_structure() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+        let annotated = tree.annotate(|polygon| polygon.area() as u32);
+
+        let mut depths: Vec<(usize, u32)> = annotated
+            .iter()
+            .map(|(depth, (area, _))| (depth, *area))
+            .collect();
+        depths.sort();
+        assert_eq!(depths, vec![(0, 25), (0, 100), (1, 4), (1, 16), (2, 1)]);
+        assert_eq!(annotated.depth_at_point([6.5, 6.5]), 3);
+        assert_eq!(annotated.bounding_rect(), tree.bounding_rect());
+    }
+
+    #[test]
+    fn bounds_consistent_detects_corruption() {
+        let mut tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+        assert!(tree.bounds_consistent());
+        assert!(Tree::from_polygon(Vec::new()).bounds_consistent());
+
+        let outer = tree
+            .root_mut()
+            .unwrap()
+            .children_mut()
+            .find(|child| child.area() == 100.0)
+            .unwrap();
+        let inner = outer.children_mut().next().unwrap();
+        inner.bounding_rect = rstar::AABB::from_corners([1.0, 1.0], [11.0, 3.0]);
+        assert!(!tree.bounds_consistent());
+    }
+
+    #[test]
+    fn iter_min_area_prunes_small_subtrees() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 3.0),
+            square(20.5, 0.5, 2.0),
+        ]);
+
+        let mut areas: Vec<(usize, f32)> = tree
+            .iter_min_area(5.0)
+            .map(|(depth, polygon)| (depth, polygon.area()))
+            .collect();
+        areas.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(areas, vec![(0, 9.0), (0, 100.0), (1, 16.0)]);
+        assert_eq!(tree.iter_min_area(0.0).count(), tree.len());
+        assert_eq!(tree.iter_min_area(1000.0).count(), 0);
+    }
+
+    #[test]
+    fn iter_bfs_and_dfs_orders() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+        ]);
+
+        let bfs: Vec<usize> = tree.iter_bfs().map(|(depth, _)| depth).collect();
+        assert_eq!(bfs, vec![0, 1, 1, 2]);
+
+        let dfs: Vec<(usize, f32)> = tree
+            .iter_dfs()
+            .map(|(depth, polygon)| (depth, polygon.area()))
+            .collect();
+        assert_eq!(dfs.len(), 4);
+        assert_eq!(dfs[0], (0, 100.0));
+        let nested = dfs.iter().position(|&entry| entry == (1, 16.0)).unwrap();
+        assert_eq!(dfs[nested + 1], (2, 1.0));
+
+        let mut bfs: Vec<(usize, f32)> = tree
+            .iter()
+            .map(|(depth, polygon)| (depth, polygon.area()))
+            .collect();
+        let mut dfs = dfs;
+        bfs.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        dfs.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(bfs, dfs);
+    }
+
+    #[test]
+    fn replace_value_at_point_swaps_tag() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(1.0, 1.0, 2.0);
+        let mut tree = Tree::from_polygon_id(vec![(1, outer.clone()), (2, inner.clone())]);
+
+        assert_eq!(
+            tree.replace_value_at_point([1.5, 1.5], (20, inner.clone())),
+            Some((2, inner.clone()))
+        );
+        assert_eq!(
+            tree.replace_value_at_point([5.0, 5.0], (10, outer.clone())),
+            Some((1, outer))
+        );
+        assert_eq!(tree.replace_value_at_point([50.0, 50.0], (3, inner)), None);
+
+        let ids: Vec<(usize, u32)> = tree.iter().map(|(depth, (id, _))| (depth, *id)).collect();
+        assert_eq!(ids, vec![(0, 10), (1, 20)]);
+    }
+
+    #[test]
+    fn common_container_of_two_points() {
+        let outer = square(0.0, 0.0, 10.0);
+        let tree = Tree::from_polygon(vec![
+            outer.clone(),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(6.0, 6.0, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        assert_eq!(tree.common_container([2.0, 2.0], [6.5, 6.5]), Some(&outer));
+        assert_eq!(
+            tree.common_container([6.5, 6.5], [8.0, 8.0]),
+            Some(&square(5.0, 5.0, 4.0))
+        );
+        assert_eq!(tree.common_container([2.0, 2.0], [21.0, 1.0]), None);
+        assert_eq!(tree.common_container([50.0, 50.0], [50.0, 50.0]), None);
+    }
+
+    #[test]
+    fn sorted_children_ignore_input_order() {
+        let mut shapes = vec![
+            square(0.0, 0.0, 20.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 4.0),
+            square(12.0, 1.0, 2.0),
+            square(12.0, 12.0, 3.0),
+        ];
+        let forward = Tree::from_polygon(shapes.clone());
+        shapes.reverse();
+        shapes.swap(1, 3);
+        let backward = Tree::from_polygon(shapes);
+
+        let top = |tree: &Tree<Polygon>| {
+            let root = tree.root().as_ref().unwrap();
+            let outer = root.children()[0];
+            let by_area: Vec<Polygon> = outer
+                .children_sorted_by_area()
+                .into_iter()
+                .map(|child| child.value().clone())
+                .collect();
+            let by_x: Vec<Polygon> = outer
+                .children_sorted(|l, r| l.center_point()[0].total_cmp(&r.center_point()[0]))
+                .into_iter()
+                .map(|child| child.value().clone())
+                .collect();
+            (by_area, by_x)
+        };
+
+        let (by_area, by_x) = top(&forward);
+        assert_eq!((by_area.clone(), by_x.clone()), top(&backward));
+        let areas: Vec<f32> = by_area.iter().map(Shape::area).collect();
+        assert_eq!(areas, vec![4.0, 4.0, 9.0, 16.0]);
+        assert_eq!(by_x[0], square(1.0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn iter_depth_range_yields_band() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 20.0),
+            square(1.0, 1.0, 10.0),
+            square(2.0, 2.0, 6.0),
+            square(3.0, 3.0, 2.0),
+            square(12.0, 12.0, 5.0),
+            square(13.0, 13.0, 1.0),
+            square(40.0, 0.0, 5.0),
+        ]);
+
+        let mut band: Vec<(usize, f32)> = tree
+            .iter_depth_range(1..=2)
+            .map(|(depth, polygon)| (depth, polygon.area()))
+            .collect();
+        band.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(band, vec![(1, 25.0), (1, 100.0), (2, 1.0), (2, 36.0)]);
+
+        assert_eq!(tree.iter_depth_range(0..=10).count(), tree.len());
+        assert_eq!(tree.iter_depth_range(4..=5).count(), 0);
+    }
+
+    #[test]
+    fn extend_sorts_batch() {
+        let mut tree = Tree::from_polygon(vec![square(0.0, 0.0, 10.0)]);
+        tree.extend(vec![
+            square(21.0, 1.0, 1.0),
+            square(1.0, 1.0, 1.0),
+            square(20.0, 0.0, 5.0),
+            square(5.0, 5.0, 3.0),
+            square(5.5, 5.5, 1.0),
+        ]);
+
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.depth_at_point([21.5, 1.5]), 2);
+        assert_eq!(tree.depth_at_point([1.5, 1.5]), 2);
+        assert_eq!(tree.depth_at_point([6.0, 6.0]), 3);
+        assert_eq!(tree.levels()[0].len(), 2);
+    }
+
+    #[test]
+    fn with_capacity_bulk_loads_on_extend() {
+        let shapes = vec![
+            square(1.0, 1.0, 1.0),
+            square(0.0, 0.0, 10.0),
+            square(20.0, 0.0, 5.0),
+            square(5.0, 5.0, 3.0),
+        ];
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+
+        let mut tree = Tree::with_capacity(empty.clone(), 2);
+        assert!(tree.is_empty());
+        tree.extend(shapes.clone());
+
+        assert_eq!(tree, Tree::from_polygon(shapes));
+        assert_eq!(tree.bounding_rect(), Some(([0.0, 0.0], [25.0, 10.0])));
+        tree.extend(vec![square(2.0, 2.0, 1.0)]);
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 2);
+    }
+
+    #[test]
+    fn stack_at_point_is_innermost_first() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 6.0),
+            square(2.0, 2.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let areas: Vec<f32> = tree
+            .stack_at_point([3.0, 3.0])
+            .into_iter()
+            .map(Shape::area)
+            .collect();
+        assert_eq!(areas, vec![4.0, 36.0, 100.0]);
+        assert_eq!(tree.stack_at_point([8.0, 8.0]).len(), 1);
+        assert!(tree.stack_at_point([50.0, 50.0]).is_empty());
+    }
+
+    #[test]
+    fn filter_map_reparents_to_kept_ancestor() {
+        let tree = Tree::from_polygon_id(vec![
+            (1, square(0.0, 0.0, 10.0)),
+            (2, square(1.0, 1.0, 6.0)),
+            (3, square(2.0, 2.0, 1.0)),
+            (4, square(4.0, 4.0, 1.0)),
+            (5, square(20.0, 0.0, 5.0)),
+        ]);
+
+        let root = (
+            0,
+            Polygon::new(geo::LineString::new(Vec::new()), Vec::new()),
+        );
+        let kept = tree.filter_map(root.clone(), |(id, polygon)| {
+            (*id != 2).then(|| (*id, polygon.clone()))
+        });
+        let mut depths: Vec<(usize, u32)> =
+            kept.iter().map(|(depth, (id, _))| (depth, *id)).collect();
+        depths.sort();
+        assert_eq!(depths, vec![(0, 1), (0, 5), (1, 3), (1, 4)]);
+        assert_eq!(kept.depth_at_point([2.5, 2.5]), 2);
+
+        let top = tree.filter_map(root, |(id, polygon)| {
+            (*id >= 3).then(|| (*id, polygon.clone()))
+        });
+        assert_eq!(top.levels()[0].len(), 3);
+        assert_eq!(top.len(), 3);
+    }
+
+    #[test]
+    fn total_and_covered_area() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(2.0, 2.0, 6.0),
+            square(3.0, 3.0, 2.0),
+            square(20.0, 0.0, 3.0),
+        ]);
+        assert_eq!(tree.total_area(), 100.0 + 36.0 + 4.0 + 9.0);
+        assert_eq!(tree.covered_area(), 100.0 - 36.0 + 4.0 + 9.0);
+
+        let donut = Tree::from_polygon(vec![square(0.0, 0.0, 10.0), square(2.0, 2.0, 6.0)]);
+        assert_eq!(donut.covered_area(), 64.0);
+        assert_eq!(Tree::from_polygon(Vec::new()).covered_area(), 0.0);
+    }
+
+    #[test]
+    fn find_depth_is_relative() {
+        let outer = square(0.0, 0.0, 10.0);
+        let middle = square(1.0, 1.0, 6.0);
+        let inner = square(2.0, 2.0, 2.0);
+        let tree = Tree::from_polygon(vec![
+            outer.clone(),
+            middle.clone(),
+            inner.clone(),
+            square(20.0, 0.0, 1.0),
+        ]);
+
+        let root = tree.root().as_ref().unwrap();
+        let top = root
+            .children()
+            .into_iter()
+            .find(|node| node.value() == &outer)
+            .unwrap();
+        assert_eq!(top.find_depth(|value| value == &outer), Some(0));
+        assert_eq!(top.find_depth(|value| value == &middle), Some(1));
+        assert_eq!(top.find_depth(|value| value == &inner), Some(2));
+        assert_eq!(top.find_depth(|value| value.area() == 1.0), None);
+        assert_eq!(root.find_depth(|value| value == &inner), Some(3));
+    }
+
+    #[test]
+    fn remove_subtree_where_takes_descendants() {
+        let outer = square(0.0, 0.0, 10.0);
+        let other = square(20.0, 0.0, 10.0);
+        let mut tree = Tree::from_polygon(vec![
+            outer.clone(),
+            square(1.0, 1.0, 6.0),
+            square(2.0, 2.0, 1.0),
+            other.clone(),
+            square(21.0, 1.0, 1.0),
+        ]);
+
+        // The squares inside `outer` also match, but go with it.
+        let removed = tree.remove_subtree_where(|polygon| polygon.exterior().0[0].x < 15.0);
+        assert_eq!(removed, vec![outer]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.depth_at_point([2.5, 2.5]), 0);
+        assert_eq!(tree.iter().next(), Some((0, &other)));
+        assert_eq!(tree.bounding_rect(), Some(([20.0, 0.0], [30.0, 10.0])));
+        assert!(tree.bounds_consistent());
+    }
+
+    #[test]
+    fn builder_defaults_match_from_polygon() {
+        let polygons = vec![
+            square(1.0, 1.0, 4.0),
+            square(0.0, 0.0, 10.0),
+            square(2.0, 2.0, 1.0),
+            square(20.0, 0.0, 5.0),
+            square(0.0, 0.0, 10.0),
+        ];
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+
+        let built = TreeBuilder::new(empty.clone()).build(polygons.clone());
+        assert!(built == Tree::from_polygon(polygons.clone()));
+        assert_eq!(built.len(), 5);
+
+        let deduped = TreeBuilder::new(empty)
+            .dedupe(true)
+            .dedupe_epsilon(1e-4)
+            .policy(ContainmentPolicy::SmallestArea)
+            .build(polygons);
+        assert_eq!(deduped.len(), 4);
+        assert_eq!(deduped.depth_at_point([2.5, 2.5]), 3);
+    }
+
+    #[test]
+    fn builder_tolerance_loosens_containment() {
+        // Pokes 1 unit out of the left edge of the container.
+        let polygons = vec![square(0.0, 0.0, 10.0), square(-1.0, 2.0, 3.0)];
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+
+        let strict = TreeBuilder::new(empty.clone()).build(polygons.clone());
+        assert_eq!(strict.levels()[0].len(), 2);
+
+        for builder in [
+            TreeBuilder::new(empty.clone()).tolerance(1.0),
+            TreeBuilder::new(empty)
+                .tolerance(1.0)
+                .policy(ContainmentPolicy::SmallestArea),
+        ] {
+            let tree = builder.build(polygons.clone());
+            assert_eq!(tree.levels()[0].len(), 1);
+            assert_eq!(tree.depth_at_point([0.5, 3.0]), 2);
+        }
+
+        let deduped = Tree::from_polygon_dedup(polygons, 1.0);
+        assert_eq!(deduped.levels()[0].len(), 2);
+    }
+
+    #[test]
+    fn dedup_by_geometry_keeps_shallowest() {
+        let outer = square(0.0, 0.0, 10.0);
+        let mut tree = Tree::from_polygon(vec![
+            outer.clone(),
+            outer.clone(),
+            square(2.0, 2.0, 2.0),
+            square(20.0, 0.0, 10.0),
+            square(21.0, 1.0, 2.0),
+            square(21.0, 1.0, 2.0),
+        ]);
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.depth_at_point([3.0, 3.0]), 3);
+
+        tree.dedup_by_geometry(1e-4);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.depth_at_point([3.0, 3.0]), 2);
+        assert_eq!(tree.depth_at_point([22.0, 2.0]), 2);
+        assert_eq!(tree.stack_at_point([3.0, 3.0])[1], &outer);
+        assert!(tree.bounds_consistent());
+    }
+
+    #[test]
+    fn structural_hash_ignores_build_order() {
+        let shapes = vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 2.0),
+            square(5.0, 5.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ];
+        let tree = Tree::from_polygon(shapes.clone());
+
+        let mut reordered = Tree::from_polygon(Vec::new());
+        for shape in shapes[1..].iter().rev() {
+            reordered.insert(shape.clone());
+        }
+        reordered.rebuild();
+        reordered.insert(shapes[0].clone());
+        reordered.rebuild();
+        assert_eq!(tree.structural_hash(), reordered.structural_hash());
+
+        let mut moved = shapes;
+        moved[2] = square(6.0, 5.0, 2.0);
+        assert_ne!(
+            tree.structural_hash(),
+            Tree::from_polygon(moved).structural_hash()
+        );
+        assert_ne!(
+            tree.structural_hash(),
+            Tree::from_polygon(Vec::new()).structural_hash()
+        );
+    }
+
+    #[test]
+    fn from_levels_round_trips() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(6.0, 6.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+        let levels = tree.to_levels();
+        assert_eq!(
+            levels.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+
+        let empty = Polygon::new(geo::LineString::new(Vec::new()), Vec::new());
+        let rebuilt = Tree::from_levels(empty.clone(), levels);
+        assert!(rebuilt == tree);
+        assert_eq!(rebuilt.structural_hash(), tree.structural_hash());
+        assert_eq!(rebuilt.depth_at_point([2.5, 2.5]), 3);
+        assert!(Tree::from_levels(empty, Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn pick_inside_or_nearest() {
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(2.0, 2.0, 2.0);
+        let right = square(20.0, 0.0, 5.0);
+        let tree = Tree::from_polygon(vec![outer.clone(), inner.clone(), right.clone()]);
+
+        assert_eq!(tree.pick([3.0, 3.0]), Some(Pick::Inside(&inner)));
+        assert_eq!(tree.pick([8.0, 8.0]), Some(Pick::Inside(&outer)));
+        assert_eq!(tree.pick([13.0, 4.0]), Some(Pick::Nearest(&outer, 3.0)));
+        assert_eq!(tree.pick([18.0, 4.0]), Some(Pick::Nearest(&right, 2.0)));
+        assert_eq!(Tree::from_polygon(Vec::new()).pick([0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn pick_measures_to_the_shape() {
+        let l_shape = Polygon::new(
+            geo::LineString::from(vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 4.0),
+                (4.0, 4.0),
+                (4.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]),
+            Vec::new(),
+        );
+        let far = square(30.0, 30.0, 2.0);
+        let tree = Tree::from_polygon(vec![l_shape.clone(), far]);
+
+        // Inside the L's bounding rect but in its notch.
+        assert_eq!(tree.pick([8.0, 8.0]), Some(Pick::Nearest(&l_shape, 4.0)));
+
+        // A nested shape on its container's edge is as near and smaller.
+        let outer = square(0.0, 0.0, 10.0);
+        let inner = square(0.0, 0.0, 2.0);
+        let tree = Tree::from_polygon(vec![outer, inner.clone()]);
+        assert_eq!(tree.pick([-1.0, 1.0]), Some(Pick::Nearest(&inner, 1.0)));
+    }
+
+    #[test]
+    fn contains_shape_samples_concave_parents() {
+        // A U open at the top, and a bar across its bottom that pokes up
+        // into the notch.
+        let parent = Polygon::new(
+            geo::LineString::from(vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (7.0, 10.0),
+                (7.0, 3.0),
+                (3.0, 3.0),
+                (3.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]),
+            Vec::new(),
+        );
+        let poking = geo::Rect::new(
+            geo::coord! { x: 1.0, y: 0.5 },
+            geo::coord! { x: 9.0, y: 4.0 },
+        )
+        .to_polygon();
+        let inside = square(1.0, 0.5, 2.0);
+
+        let point = geo::InteriorPoint::interior_point(&poking).unwrap();
+        assert!(geo::Contains::contains(&parent, &point));
+        assert!(!parent.contains_shape(&poking));
+        assert!(parent.contains_shape(&inside));
+
+        let samples = poking.interior_sample_points(9);
+        assert!(samples.len() > 1 && samples.len() <= 9);
+        assert!(samples.iter().all(|&point| poking.contains_point(point)));
+
+        let tree = Tree::from_polygon(vec![parent, poking, inside]);
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn relocate_at_point_reparents() {
+        let mut tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(20.0, 0.0, 10.0),
+            square(2.0, 2.0, 2.0),
+            square(2.5, 2.5, 1.0),
+        ]);
+        assert_eq!(tree.depth_at_point([3.0, 3.0]), 3);
+
+        assert!(tree.relocate_at_point([2.2, 2.2], [1.0, 0.0, 0.0, 1.0, 20.0, 0.0]));
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.depth_at_point([3.0, 3.0]), 1);
+        assert_eq!(tree.depth_at_point([23.0, 3.0]), 3);
+        assert_eq!(
+            tree.deepest_at_point([22.2, 2.2]),
+            Some(&square(22.0, 2.0, 2.0))
+        );
+        assert!(tree.bounds_consistent());
+
+        assert!(!tree.relocate_at_point([50.0, 50.0], [1.0, 0.0, 0.0, 1.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn relocate_at_point_moves_the_node_it_found() {
+        // Overlapping top-level siblings that all contain the point, spread
+        // over several R-tree nodes.
+        let mut tree = Tree::from_polygon(
+            (0..25)
+                .map(|i| square((i % 5 * 2) as f64 - 2.0, (i / 5 * 2) as f64 - 2.0, 10.0))
+                .collect(),
+        );
+        let deepest = tree.deepest_at_point([7.0, 7.0]).cloned().unwrap();
+        let others: Vec<Polygon> = tree
+            .iter()
+            .map(|(_, value)| value.clone())
+            .filter(|value| *value != deepest)
+            .collect();
+
+        assert!(tree.relocate_at_point([7.0, 7.0], [1.0, 0.0, 0.0, 1.0, 100.0, 0.0]));
+        assert_eq!(tree.levels()[0].len(), 25);
+        assert_eq!(
+            tree.deepest_at_point([107.0, 7.0]),
+            Some(&deepest.transform([1.0, 0.0, 0.0, 1.0, 100.0, 0.0]))
+        );
+        for other in &others {
+            assert!(tree.iter().any(|(_, value)| value == other));
+        }
+        assert!(tree.bounds_consistent());
+    }
+
+    #[test]
+    fn root_accepts_shapes_it_does_not_contain() {
+        let root = square(0.0, 0.0, 10.0);
+        let outside = square(20.0, 0.0, 5.0);
+        let larger = square(-10.0, -10.0, 40.0);
+        let mut tree = Tree::with_root(root.clone(), vec![outside.clone(), square(1.0, 1.0, 2.0)]);
+
+        assert!(!root.contains_shape(&outside));
+        assert_eq!(tree.depth_at_point([21.0, 1.0]), 1);
+        assert!(!tree.root_contains_point([21.0, 1.0]));
+
+        tree.insert(larger.clone());
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.depth_at_point([-5.0, -5.0]), 1);
+        assert_eq!(tree.root().as_ref().unwrap().value(), &root);
+
+        let from = Tree::from((vec![larger, outside], root));
+        let depths: Vec<usize> = from.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+    }
+
+    #[test]
+    fn iter_leaves_postorder_children_first() {
+        let outer = square(0.0, 0.0, 10.0);
+        let a = square(1.0, 1.0, 4.0);
+        let a1 = square(2.0, 2.0, 1.0);
+        let b = square(6.0, 6.0, 2.0);
+        let right = square(20.0, 0.0, 5.0);
+        let tree = Tree::from_polygon(vec![
+            a1.clone(),
+            outer.clone(),
+            right.clone(),
+            b.clone(),
+            a.clone(),
+        ]);
+
+        let order: Vec<(usize, &Polygon)> = tree.iter_leaves_postorder().collect();
+        assert_eq!(
+            order,
+            vec![(0, &right), (1, &b), (2, &a1), (1, &a), (0, &outer)]
+        );
+        assert_eq!(
+            Tree::from_polygon(Vec::new())
+                .iter_leaves_postorder()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn open_lines_are_leaves() {
+        use geo::{Geometry, LineString};
+
+        let guide = LineString::from(vec![(2.0, 2.0), (8.0, 3.0), (5.0, 8.0)]);
+        let tree = TreeBuilder::new(Geometry::Polygon(Polygon::new(
+            LineString::new(Vec::new()),
+            Vec::new(),
+        )))
+        .build(vec![
+            Geometry::LineString(guide.clone()),
+            Geometry::Polygon(square(0.0, 0.0, 10.0)),
+            Geometry::Polygon(square(20.0, 0.0, 5.0)),
+        ]);
+        let nested: Vec<(usize, bool)> = tree
+            .iter()
+            .map(|(depth, geometry)| (depth, matches!(geometry, Geometry::LineString(_))))
+            .collect();
+        assert_eq!(nested, vec![(0, false), (0, false), (1, true)]);
+        assert_eq!(tree.depth_at_point([5.0, 2.5]), 1);
+
+        assert!(!guide.contains_shape(&LineString::from(vec![(3.0, 3.0), (4.0, 3.0)])));
+        assert!(!guide.contains_point([5.0, 2.5]));
+        assert_eq!(guide.area(), 0.0);
+
+        let lines = Tree::from((
+            vec![
+                guide.clone(),
+                LineString::from(vec![(20.0, 0.0), (30.0, 0.0)]),
+            ],
+            LineString::new(Vec::new()),
+        ));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines.pick([5.0, 9.0]), Some(Pick::Nearest(&guide, 1.0)));
+    }
+
+    #[test]
+    fn leaf_and_internal_counts_sum_to_len() {
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            square(1.0, 1.0, 4.0),
+            square(2.0, 2.0, 1.0),
+            square(6.0, 6.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+        assert_eq!(tree.leaf_count(), 3);
+        assert_eq!(tree.internal_count(), 2);
+        assert_eq!(tree.leaf_count() + tree.internal_count(), tree.len());
+
+        let empty = Tree::from_polygon(Vec::new());
+        assert_eq!((empty.leaf_count(), empty.internal_count()), (0, 0));
+    }
+
+    #[test]
+    fn bbox_only_hits_finds_concavities() {
+        // A U open at the top, with a square in its left arm.
+        let concave = Polygon::new(
+            geo::LineString::from(vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (7.0, 10.0),
+                (7.0, 3.0),
+                (3.0, 3.0),
+                (3.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]),
+            Vec::new(),
+        );
+        let tree = Tree::from_polygon(vec![
+            concave.clone(),
+            square(0.5, 4.0, 2.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        assert_eq!(tree.bbox_only_hits([5.0, 6.0]), vec![&concave]);
+        assert_eq!(tree.bbox_only_hits([2.4, 4.1]), Vec::<&Polygon>::new());
+        assert!(tree.bbox_only_hits([50.0, 50.0]).is_empty());
+    }
+
+    #[test]
+    fn subtree_at_point_borrows_region() {
+        let middle = square(1.0, 1.0, 6.0);
+        let tree = Tree::from_polygon(vec![
+            square(0.0, 0.0, 10.0),
+            middle.clone(),
+            square(2.0, 2.0, 1.0),
+            square(4.0, 4.0, 2.0),
+            square(4.5, 4.5, 1.0),
+            square(20.0, 0.0, 5.0),
+        ]);
+
+        let node = tree.subtree_at_point([1.5, 1.5]).unwrap();
+        assert_eq!(node.value(), &middle);
+        assert_eq!(node.descendant_count(), 3);
+        let mut below: Vec<(usize, f32)> = node
+            .iter()
+            .map(|(depth, polygon)| (depth, polygon.area()))
+            .collect();
+        below.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(below, vec![(0, 1.0), (0, 4.0), (1, 1.0)]);
+
+        assert!(tree.subtree_at_point([50.0, 50.0]).is_none());
     }
 }
@@ -0,0 +1,46 @@
+/// Palette stops from dark blue through green to yellow.
+pub const DEPTH_PALETTE: [[u8; 4]; 5] = [
+    [68, 1, 84, 255],
+    [59, 82, 139, 255],
+    [33, 145, 140, 255],
+    [94, 201, 98, 255],
+    [253, 231, 37, 255],
+];
+
+/// RGBA for `depth` in a tree `max_depth` deep, interpolated linearly across
+/// [`DEPTH_PALETTE`]. Depth 0 maps to the first stop and `max_depth` (or
+/// anything deeper) to the last.
+pub fn depth_color(depth: usize, max_depth: usize) -> [u8; 4] {
+    if max_depth == 0 {
+        return DEPTH_PALETTE[0];
+    }
+
+    let t = depth.min(max_depth) as f32 / max_depth as f32;
+    let position = t * (DEPTH_PALETTE.len() - 1) as f32;
+    let index = (crate::float::floor(position) as usize).min(DEPTH_PALETTE.len() - 2);
+    let fraction = position - index as f32;
+
+    let (from, to) = (DEPTH_PALETTE[index], DEPTH_PALETTE[index + 1]);
+    let mut color = [0; 4];
+    for channel in 0..4 {
+        color[channel] = crate::float::round(
+            from[channel] as f32 + (to[channel] as f32 - from[channel] as f32) * fraction,
+        ) as u8;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn depth_color_hits_palette_endpoints() {
+        assert_eq!(depth_color(0, 4), DEPTH_PALETTE[0]);
+        assert_eq!(depth_color(4, 4), DEPTH_PALETTE[4]);
+        assert_eq!(depth_color(2, 4), DEPTH_PALETTE[2]);
+        assert_eq!(depth_color(9, 4), DEPTH_PALETTE[4]);
+        assert_eq!(depth_color(0, 0), DEPTH_PALETTE[0]);
+        assert_eq!(depth_color(1, 8), [64, 42, 112, 255]);
+    }
+}
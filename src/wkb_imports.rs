@@ -0,0 +1,204 @@
+#[cfg(feature = "wkb-integration")]
+use geo::{Coord, LineString, Polygon};
+
+/// Decodes a WKB `Polygon`, `MultiPolygon` or `GeometryCollection` into
+/// polygons for `Tree::from_polygon`. Either byte order is accepted, as are
+/// ISO and PostGIS (EWKB) Z/M variants, whose extra ordinates are dropped.
+/// Non-polygonal members of a collection are skipped; any other geometry
+/// type is an error.
+#[cfg(feature = "wkb-integration")]
+pub fn import_wkb(bytes: &[u8]) -> Result<Vec<Polygon>, String> {
+    let mut reader = WkbReader { bytes, pos: 0 };
+    let mut polygons = Vec::new();
+    reader.read_geometry(&mut polygons, true)?;
+    Ok(polygons)
+}
+
+#[cfg(feature = "wkb-integration")]
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(feature = "wkb-integration")]
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| format!("WKB ended early at byte {}", self.pos))?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u32(&mut self, little_endian: bool) -> Result<u32, String> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self, little_endian: bool) -> Result<f64, String> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    /// Reads a count of items of `item_size` bytes each, rejecting counts
+    /// that cannot fit in the rest of the input before anything is
+    /// allocated for them.
+    fn count(&mut self, little_endian: bool, item_size: usize) -> Result<usize, String> {
+        let count = self.u32(little_endian)? as usize;
+        if count.saturating_mul(item_size) > self.bytes.len() - self.pos {
+            return Err(format!(
+                "WKB ended early: {} items at byte {} do not fit",
+                count, self.pos
+            ));
+        }
+        Ok(count)
+    }
+
+    fn read_geometry(
+        &mut self,
+        polygons: &mut Vec<Polygon>,
+        top_level: bool,
+    ) -> Result<(), String> {
+        let little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            order => return Err(format!("Invalid WKB byte order {}", order)),
+        };
+
+        // EWKB flags Z, M and an embedded SRID in the high bits; ISO WKB adds
+        // 1000 for Z, 2000 for M and 3000 for ZM.
+        let code = self.u32(little_endian)?;
+        let mut dimensions = 2 + (code >> 31) as usize + ((code >> 30) & 1) as usize;
+        if code & 0x2000_0000 != 0 {
+            self.u32(little_endian)?;
+        }
+        let code = code & 0x0fff_ffff;
+        dimensions += match code / 1000 {
+            0 => 0,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(format!("Unsupported WKB geometry type {}", code)),
+        };
+
+        let kind = code % 1000;
+        if top_level && !matches!(kind, 3 | 6 | 7) {
+            return Err(format!(
+                "Expected Polygon or MultiPolygon WKB, found geometry type {}",
+                kind
+            ));
+        }
+
+        match kind {
+            1 => {
+                self.read_coord(little_endian, dimensions)?;
+            }
+            2 => {
+                self.read_line_string(little_endian, dimensions)?;
+            }
+            3 => polygons.push(self.read_polygon(little_endian, dimensions)?),
+            4..=7 => {
+                for _ in 0..self.count(little_endian, 5)? {
+                    self.read_geometry(polygons, false)?;
+                }
+            }
+            _ => return Err(format!("Unsupported WKB geometry type {}", kind)),
+        }
+        Ok(())
+    }
+
+    fn read_coord(&mut self, little_endian: bool, dimensions: usize) -> Result<Coord, String> {
+        let x = self.f64(little_endian)?;
+        let y = self.f64(little_endian)?;
+        for _ in 2..dimensions {
+            self.f64(little_endian)?;
+        }
+        Ok(Coord { x, y })
+    }
+
+    fn read_line_string(
+        &mut self,
+        little_endian: bool,
+        dimensions: usize,
+    ) -> Result<LineString, String> {
+        let count = self.count(little_endian, 8 * dimensions)?;
+        (0..count)
+            .map(|_| self.read_coord(little_endian, dimensions))
+            .collect()
+    }
+
+    fn read_polygon(&mut self, little_endian: bool, dimensions: usize) -> Result<Polygon, String> {
+        let count = self.count(little_endian, 4)?;
+        let mut rings = (0..count)
+            .map(|_| self.read_line_string(little_endian, dimensions))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+
+        let exterior = rings.next().unwrap_or_else(|| LineString::new(Vec::new()));
+        Ok(Polygon::new(exterior, rings.collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn big_endian_polygon(min: f64, max: f64) -> Vec<u8> {
+        let mut bytes = vec![0];
+        bytes.extend(3u32.to_be_bytes());
+        bytes.extend(1u32.to_be_bytes());
+        bytes.extend(5u32.to_be_bytes());
+        for (x, y) in [(min, min), (max, min), (max, max), (min, max), (min, min)] {
+            bytes.extend(x.to_be_bytes());
+            bytes.extend(y.to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn import_wkb_polygons() {
+        // POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0)), little endian.
+        let polygons = import_wkb(&from_hex(
+            "0103000000010000000500000000000000000000000000000000000000000000000000244000000000000000000000000000002440000000000000244000000000000000000000000000002440000000000000000000000000000000000000",
+        ))
+        .unwrap();
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].area(), 100.0);
+
+        let mut multi = vec![0];
+        multi.extend(6u32.to_be_bytes());
+        multi.extend(2u32.to_be_bytes());
+        multi.extend(big_endian_polygon(0.0, 10.0));
+        multi.extend(big_endian_polygon(1.0, 2.0));
+        let tree = Tree::from_polygon(import_wkb(&multi).unwrap());
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+
+        assert!(
+            import_wkb(&multi[..40])
+                .unwrap_err()
+                .contains("ended early")
+        );
+        assert!(
+            import_wkb(&from_hex("0101000000"))
+                .unwrap_err()
+                .contains("type 1")
+        );
+        assert!(import_wkb(&[7]).unwrap_err().contains("byte order"));
+    }
+}
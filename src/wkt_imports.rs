@@ -0,0 +1,67 @@
+#[cfg(feature = "wkt-integration")]
+use geo::{Geometry, Polygon};
+
+/// Parses a WKT `POLYGON`, `MULTIPOLYGON` or `GEOMETRYCOLLECTION` into
+/// polygons for `Tree::from_polygon`. Non-polygonal members of a collection
+/// are skipped; any other geometry type is an error.
+#[cfg(feature = "wkt-integration")]
+pub fn import_wkt(wkt: &str) -> Result<Vec<Polygon>, String> {
+    use wkt::TryFromWkt;
+
+    let geometry = Geometry::<f64>::try_from_wkt_str(wkt)
+        .map_err(|e| format!("Could not parse WKT: {}", e))?;
+
+    match geometry {
+        Geometry::Polygon(_) | Geometry::MultiPolygon(_) | Geometry::GeometryCollection(_) => {
+            let mut polygons = Vec::new();
+            push_polygons(geometry, &mut polygons);
+            Ok(polygons)
+        }
+        _ => Err(format!(
+            "Expected POLYGON or MULTIPOLYGON WKT, found {}",
+            wkt.trim_start()
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .next()
+                .unwrap_or_default()
+        )),
+    }
+}
+
+#[cfg(feature = "wkt-integration")]
+fn push_polygons(geometry: Geometry, polygons: &mut Vec<Polygon>) {
+    match geometry {
+        Geometry::Polygon(polygon) => polygons.push(polygon),
+        Geometry::MultiPolygon(multi_polygon) => polygons.extend(multi_polygon),
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_polygons(geometry, polygons);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn import_wkt_polygons() {
+        let polygons = import_wkt(
+            "MULTIPOLYGON (((0 0, 10 0, 10 10, 0 10, 0 0)), ((1 1, 2 1, 2 2, 1 2, 1 1)))",
+        )
+        .unwrap();
+        assert_eq!(polygons.len(), 2);
+
+        let tree = Tree::from_polygon(polygons);
+        let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, vec![0, 1]);
+
+        assert_eq!(
+            import_wkt("POLYGON ((0 0, 1 0, 1 1, 0 0))").unwrap().len(),
+            1
+        );
+        assert!(import_wkt("POLYGON ((0 0, 1 0").is_err());
+        assert!(import_wkt("POINT (1 1)").unwrap_err().contains("POINT"));
+    }
+}
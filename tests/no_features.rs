@@ -0,0 +1,70 @@
+//! Exercises the core with the built-in shapes only, so it also runs under
+//! `cargo test --no-default-features`, where the library is `no_std`.
+
+use depth_tree::{Circle, Rect, Shape, Tree};
+
+#[test]
+fn rect_tree_without_features() {
+    let tree: Tree<Rect> = vec![
+        Rect::new([1.0, 1.0], [4.0, 4.0]),
+        Rect::new([0.0, 0.0], [10.0, 10.0]),
+        Rect::new([2.0, 2.0], [3.0, 3.0]),
+        Rect::new([20.0, 0.0], [25.0, 5.0]),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(tree.len(), 4);
+    assert_eq!(tree.depth_at_point([2.5, 2.5]), 3);
+    assert_eq!(tree.depth_at_point([22.0, 1.0]), 1);
+    assert_eq!(tree.depth_at_point([50.0, 50.0]), 0);
+    assert_eq!(
+        tree.deepest_at_point([2.5, 2.5]),
+        Some(&Rect::new([2.0, 2.0], [3.0, 3.0]))
+    );
+    assert_eq!(tree.bounding_rect(), Some(([0.0, 0.0], [25.0, 10.0])));
+}
+
+#[test]
+fn circle_tree_without_features() {
+    let tree: Tree<Circle> = vec![
+        Circle::new([0.0, 0.0], 10.0),
+        Circle::new([3.0, 0.0], 2.0),
+        Circle::new([-3.0, 0.0], 2.0),
+    ]
+    .into_iter()
+    .collect();
+
+    let depths: Vec<usize> = tree.iter().map(|(depth, _)| depth).collect();
+    assert_eq!(depths, vec![0, 1, 1]);
+    assert_eq!(tree.depth_at_point([3.0, 0.5]), 2);
+    assert_eq!(
+        tree.deepest_at_point([0.0, 5.0]).map(Shape::area),
+        Some(100.0 * std::f32::consts::PI)
+    );
+}
+
+#[test]
+fn iterators_without_std() {
+    let mut tree = Tree::from((
+        vec![
+            Rect::new([0.0, 0.0], [10.0, 10.0]),
+            Rect::new([1.0, 1.0], [4.0, 4.0]),
+            Rect::new([6.0, 6.0], [8.0, 8.0]),
+        ],
+        Rect::default(),
+    ));
+    tree.insert(Rect::new([2.0, 2.0], [3.0, 3.0]));
+
+    let dfs: Vec<usize> = tree.iter_dfs().map(|(depth, _)| depth).collect();
+    assert_eq!(dfs.len(), 4);
+    assert_eq!(dfs[0], 0);
+    assert_eq!(
+        tree.levels().iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![1, 2, 1]
+    );
+    assert_eq!(
+        tree.stack_at_point([2.5, 2.5]).first(),
+        Some(&&Rect::new([2.0, 2.0], [3.0, 3.0]))
+    );
+}